@@ -6,7 +6,7 @@ use crate::{
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct AlbumResult {
     pub id: AlbumId,
@@ -32,21 +32,21 @@ pub struct Album {
     pub kind: String,
 }
 
-impl Into<Album> for AlbumResult {
-    fn into(self) -> Album {
+impl From<AlbumResult> for Album {
+    fn from(value: AlbumResult) -> Self {
         Album {
-            id: self.id,
-            title: self.title,
-            release_date: self.release_date,
-            artist: self.artists.first().cloned().unwrap_or_else(|| Artist {
+            id: value.id,
+            title: value.title,
+            release_date: value.release_date,
+            artist: value.artists.first().cloned().unwrap_or_else(|| Artist {
                 id: ArtistId::from(0),
                 name: "Unknown Artist".to_string(),
                 kind: "MAIN".to_string(),
             }),
-            artists: self.artists,
+            artists: value.artists,
             tracks: Vec::new(),
-            cover: self.cover,
-            kind: self.kind,
+            cover: value.cover,
+            kind: value.kind,
         }
     }
 }
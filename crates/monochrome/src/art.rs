@@ -0,0 +1,81 @@
+use serde::{Deserialize, Serialize};
+
+/// the square sizes artwork is served in. not every image has every size, album covers usually go
+/// up to 1280 while artist pictures stop at 750, so [`ArtSize::fallbacks`] is tried in order when
+/// one is missing
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Default, Serialize, Deserialize,
+)]
+pub enum ArtSize {
+    #[serde(rename = "80")]
+    Px80,
+    #[serde(rename = "160")]
+    Px160,
+    #[serde(rename = "320")]
+    Px320,
+    #[serde(rename = "480")]
+    Px480,
+    #[serde(rename = "640")]
+    Px640,
+    #[serde(rename = "750")]
+    Px750,
+    #[serde(rename = "1080")]
+    Px1080,
+    #[default]
+    #[serde(rename = "1280")]
+    Px1280,
+    /// whatever was uploaded, which can be much larger than 1280
+    #[serde(rename = "origin")]
+    Origin,
+}
+
+impl ArtSize {
+    const ALL: [ArtSize; 9] = [
+        ArtSize::Px80,
+        ArtSize::Px160,
+        ArtSize::Px320,
+        ArtSize::Px480,
+        ArtSize::Px640,
+        ArtSize::Px750,
+        ArtSize::Px1080,
+        ArtSize::Px1280,
+        ArtSize::Origin,
+    ];
+
+    /// the file name on the resources server
+    pub fn file_name(&self) -> &'static str {
+        match self {
+            ArtSize::Px80 => "80x80.jpg",
+            ArtSize::Px160 => "160x160.jpg",
+            ArtSize::Px320 => "320x320.jpg",
+            ArtSize::Px480 => "480x480.jpg",
+            ArtSize::Px640 => "640x640.jpg",
+            ArtSize::Px750 => "750x750.jpg",
+            ArtSize::Px1080 => "1080x1080.jpg",
+            ArtSize::Px1280 => "1280x1280.jpg",
+            ArtSize::Origin => "origin.jpg",
+        }
+    }
+
+    /// this size, then every smaller one from largest to smallest, then the larger ones. getting
+    /// something close to what was asked for beats getting nothing
+    pub fn fallbacks(self) -> impl Iterator<Item = ArtSize> {
+        let smaller = Self::ALL.into_iter().filter(move |s| *s < self).rev();
+        let larger = Self::ALL.into_iter().filter(move |s| *s > self);
+
+        std::iter::once(self).chain(smaller).chain(larger)
+    }
+}
+
+impl std::fmt::Display for ArtSize {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ArtSize::Origin => write!(f, "origin"),
+            size => write!(
+                f,
+                "{}",
+                size.file_name().split('x').next().unwrap_or_default()
+            ),
+        }
+    }
+}
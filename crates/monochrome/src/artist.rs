@@ -1,5 +1,6 @@
-use crate::id::ArtistId;
+use crate::{album::AlbumResult, id::ArtistId};
 use serde::{Deserialize, Serialize};
+use uuid::Uuid;
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(rename_all = "camelCase")]
@@ -9,3 +10,40 @@ pub struct Artist {
     #[serde(rename = "type")]
     pub kind: String,
 }
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ArtistDetails {
+    pub id: ArtistId,
+    pub name: String,
+    #[serde(default, deserialize_with = "crate::null_on_error")]
+    pub picture: Option<Uuid>,
+    #[serde(default)]
+    pub popularity: u32,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Discography {
+    pub artist: ArtistDetails,
+    pub albums: Vec<AlbumResult>,
+    pub singles: Vec<AlbumResult>,
+    pub compilations: Vec<AlbumResult>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArtistAlbumFilter {
+    Albums,
+    EpsAndSingles,
+    Compilations,
+}
+
+impl ArtistAlbumFilter {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ArtistAlbumFilter::Albums => "ALBUMS",
+            ArtistAlbumFilter::EpsAndSingles => "EPSANDSINGLES",
+            ArtistAlbumFilter::Compilations => "COMPILATIONS",
+        }
+    }
+}
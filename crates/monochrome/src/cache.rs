@@ -0,0 +1,256 @@
+use std::{
+    collections::HashMap,
+    future::Future,
+    path::PathBuf,
+    sync::{
+        Mutex,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::{Duration, SystemTime},
+};
+
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+
+use crate::{MonochromeError, fixture::fnv1a};
+
+/// what a cached response is, which decides how long it stays fresh
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CacheKind {
+    Album,
+    Track,
+    Search,
+    Artist,
+}
+
+impl CacheKind {
+    fn as_str(&self) -> &'static str {
+        match self {
+            CacheKind::Album => "album",
+            CacheKind::Track => "track",
+            CacheKind::Search => "search",
+            CacheKind::Artist => "artist",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CacheConfig {
+    /// how many responses are kept in memory before the least recently used ones are dropped
+    pub capacity: usize,
+    /// where to keep responses across restarts, if anywhere
+    pub dir: Option<PathBuf>,
+    pub album_ttl: Duration,
+    pub track_ttl: Duration,
+    /// search results change as things get released, so these go stale sooner
+    pub search_ttl: Duration,
+    pub artist_ttl: Duration,
+}
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        Self {
+            capacity: 512,
+            dir: None,
+            album_ttl: Duration::from_hours(24),
+            track_ttl: Duration::from_hours(24),
+            search_ttl: Duration::from_mins(10),
+            artist_ttl: Duration::from_hours(6),
+        }
+    }
+}
+
+impl CacheConfig {
+    pub fn ttl(&self, kind: CacheKind) -> Duration {
+        match kind {
+            CacheKind::Album => self.album_ttl,
+            CacheKind::Track => self.track_ttl,
+            CacheKind::Search => self.search_ttl,
+            CacheKind::Artist => self.artist_ttl,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Entry {
+    // two keys can hash to the same file, so the file says which one it holds
+    key: String,
+    stored_at: SystemTime,
+    value: serde_json::Value,
+}
+
+#[derive(Debug)]
+struct Lru {
+    entries: HashMap<(CacheKind, String), (Entry, u64)>,
+    // bumped on every access, the entry with the lowest stamp is the least recently used
+    clock: u64,
+}
+
+/// an in-memory lru in front of an optional directory of json files, keyed by kind and whatever
+/// identifies the request
+#[derive(Debug)]
+pub(crate) struct MetadataCache {
+    config: CacheConfig,
+    lru: Mutex<Lru>,
+}
+
+impl MetadataCache {
+    pub(crate) fn new(config: CacheConfig) -> Self {
+        Self {
+            config,
+            lru: Mutex::new(Lru {
+                entries: HashMap::new(),
+                clock: 0,
+            }),
+        }
+    }
+
+    /// the cached value if there's a fresh one, otherwise whatever `fetch` returns, which is
+    /// then cached. errors are never cached
+    pub(crate) async fn get_or_fetch<T, F, Fut>(
+        &self,
+        kind: CacheKind,
+        key: String,
+        fetch: F,
+    ) -> Result<T, MonochromeError>
+    where
+        T: Serialize + DeserializeOwned,
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<T, MonochromeError>>,
+    {
+        if let Some(value) = self.get(kind, &key).await {
+            match serde_json::from_value(value) {
+                Ok(value) => {
+                    tracing::debug!(kind = kind.as_str(), %key, "cache hit");
+                    return Ok(value);
+                }
+                // most likely written by an older version with a different model
+                Err(e) => {
+                    tracing::debug!(kind = kind.as_str(), %key, error = %e, "discarding unreadable cache entry")
+                }
+            }
+        }
+
+        let value = fetch().await?;
+        self.insert(kind, key, serde_json::to_value(&value)?).await;
+        Ok(value)
+    }
+
+    async fn get(&self, kind: CacheKind, key: &str) -> Option<serde_json::Value> {
+        let ttl = self.config.ttl(kind);
+
+        {
+            let mut lru = self.lru.lock().unwrap();
+            lru.clock += 1;
+            let clock = lru.clock;
+
+            let cache_key = (kind, key.to_string());
+            match lru.entries.get_mut(&cache_key) {
+                Some((entry, _)) if is_expired(entry, ttl) => {
+                    lru.entries.remove(&cache_key);
+                }
+                Some((entry, used)) => {
+                    *used = clock;
+                    return Some(entry.value.clone());
+                }
+                None => {}
+            }
+        }
+
+        let path = self.path(kind, key)?;
+        let entry: Entry = serde_json::from_slice(&tokio::fs::read(&path).await.ok()?).ok()?;
+        if entry.key != key {
+            return None;
+        }
+
+        if is_expired(&entry, ttl) {
+            tokio::fs::remove_file(&path).await.ok();
+            return None;
+        }
+
+        let value = entry.value.clone();
+        self.insert_memory(kind, key.to_string(), entry);
+        Some(value)
+    }
+
+    async fn insert(&self, kind: CacheKind, key: String, value: serde_json::Value) {
+        let entry = Entry {
+            key: key.clone(),
+            stored_at: SystemTime::now(),
+            value,
+        };
+
+        if let Some(path) = self.path(kind, &key)
+            && let Err(e) = write_entry(&path, &entry).await
+        {
+            tracing::warn!(path = %path.display(), error = %e, "failed to write cache entry");
+        }
+
+        self.insert_memory(kind, key, entry);
+    }
+
+    fn insert_memory(&self, kind: CacheKind, key: String, entry: Entry) {
+        if self.config.capacity == 0 {
+            return;
+        }
+
+        let mut lru = self.lru.lock().unwrap();
+        lru.clock += 1;
+        let clock = lru.clock;
+        let slot = (kind, key);
+
+        // replacing an entry doesn't take up any more room
+        if !lru.entries.contains_key(&slot)
+            && lru.entries.len() >= self.config.capacity
+            && let Some(oldest) = lru
+                .entries
+                .iter()
+                .min_by_key(|(_, (_, used))| *used)
+                .map(|(k, _)| k.clone())
+        {
+            lru.entries.remove(&oldest);
+        }
+
+        lru.entries.insert(slot, (entry, clock));
+    }
+
+    fn path(&self, kind: CacheKind, key: &str) -> Option<PathBuf> {
+        let dir = self.config.dir.as_ref()?;
+
+        // keys can be search queries, so they're hashed rather than used as file names
+        Some(
+            dir.join(kind.as_str())
+                .join(format!("{:016x}.json", fnv1a(key))),
+        )
+    }
+}
+
+fn is_expired(entry: &Entry, ttl: Duration) -> bool {
+    entry
+        .stored_at
+        .elapsed()
+        .map_or(true, |elapsed| elapsed > ttl)
+}
+
+/// writes to a temporary file first and renames it over `path`, so a reader never sees half an
+/// entry and two writers of the same key don't interleave
+async fn write_entry(path: &std::path::Path, entry: &Entry) -> Result<(), MonochromeError> {
+    static WRITES: AtomicU64 = AtomicU64::new(0);
+
+    if let Some(parent) = path.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+
+    let tmp = path.with_extension(format!(
+        "{}.{}.tmp",
+        std::process::id(),
+        WRITES.fetch_add(1, Ordering::Relaxed)
+    ));
+
+    if let Err(e) = tokio::fs::write(&tmp, serde_json::to_vec(entry)?).await {
+        tokio::fs::remove_file(&tmp).await.ok();
+        return Err(e.into());
+    }
+
+    tokio::fs::rename(&tmp, path).await?;
+    Ok(())
+}
@@ -0,0 +1,188 @@
+use std::time::{Duration, Instant};
+
+use futures::StreamExt;
+use reqwest::{StatusCode, Url, header};
+
+use crate::{
+    MonochromeError, RequestKind,
+    download::fetch_segment,
+    manifest::{Manifest, ManifestKind},
+    quality::AudioQuality,
+    ratelimit::{self, RateLimiter},
+    response::MonochromeResponse,
+    timeout::TimeoutPolicy,
+    track::TrackManifest,
+};
+
+/// the track every streaming mirror is probed with
+pub(crate) const PROBE_TRACK: u64 = 109485855;
+
+// enough audio to get past connection setup, small enough that scanning stays quick
+const THROUGHPUT_BYTES: u64 = 256 * 1024;
+
+// how many 429s a single tier waits out before the probe gives up on the mirror
+const RATE_LIMITED_RETRIES: u32 = 2;
+
+/// what a streaming mirror was seen to serve during the last scan
+#[derive(Debug, Clone, PartialEq)]
+pub struct Capabilities {
+    /// the best tier it served as a full track, `None` if it only ever served previews
+    pub max_quality: Option<AudioQuality>,
+    /// whether any tier came back as a preview, which usually means the mirror's account
+    /// doesn't cover the tiers above `max_quality`
+    pub previews: bool,
+    /// how `max_quality` was delivered
+    pub manifest: Option<ManifestKind>,
+    /// bytes per second, measured on the start of the audio for `max_quality`
+    pub throughput: Option<f64>,
+}
+
+impl Capabilities {
+    pub fn full_tracks(&self) -> bool {
+        self.max_quality.is_some()
+    }
+
+    /// whether the mirror can serve a full track in `quality`
+    pub fn serves(&self, quality: AudioQuality) -> bool {
+        self.max_quality
+            .is_some_and(|max| max.ladder().any(|tier| tier == quality))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct Probe {
+    /// time to the first response, the old sole measure of a mirror
+    pub(crate) latency: Duration,
+    pub(crate) capabilities: Capabilities,
+}
+
+/// asks for the probe track in every tier, best first, until one comes back in full, then
+/// downloads the start of it. a tier being refused or previewed doesn't fail the probe, the mirror
+/// not answering, or still rate limiting us after waiting, does
+pub(crate) async fn probe(
+    client: &reqwest::Client,
+    timeouts: &TimeoutPolicy,
+    limiter: &RateLimiter,
+    base: &Url,
+) -> Result<Probe, MonochromeError> {
+    let metadata = timeouts.metadata;
+    let mut latency = None;
+    let mut previews = false;
+
+    for tier in AudioQuality::HiResLossless.ladder() {
+        let mut throttled = 0;
+        let (res, latency) = loop {
+            let request = client.get(base.join("track")?).query(&[
+                ("id", PROBE_TRACK.to_string().as_str()),
+                ("quality", tier.as_str()),
+            ]);
+
+            // every tier is a request against the mirror's budget, same as any other
+            limiter.acquire(base).await;
+
+            let start = Instant::now();
+            let res = metadata.send(request).await?;
+            let latency = *latency.get_or_insert(start.elapsed());
+
+            if res.status() != StatusCode::TOO_MANY_REQUESTS {
+                break (res, latency);
+            }
+
+            // a 429 says nothing about the tier, so it's asked for again once the mirror lets us
+            limiter.back_off(base, ratelimit::retry_after(&res));
+            if throttled == RATE_LIMITED_RETRIES {
+                break (res, latency);
+            }
+            throttled += 1;
+        };
+
+        let res = match metadata.check(res, RequestKind::Probe).await {
+            Ok(res) => res,
+            Err(e) if !e.is_mirror_fault() && !e.is_retryable() => {
+                tracing::debug!(%base, quality = %tier, error = %e, "mirror refused tier");
+                continue;
+            }
+            Err(e) => return Err(e),
+        };
+
+        let manifest = metadata
+            .json::<MonochromeResponse<TrackManifest>>(res)
+            .await?
+            .data;
+
+        if manifest.asset_presentation == "PREVIEW" {
+            tracing::debug!(%base, quality = %tier, "mirror only serves a preview of tier");
+            previews = true;
+            continue;
+        }
+
+        let throughput = match throughput(client, timeouts, &manifest).await {
+            Ok(throughput) => Some(throughput),
+            Err(e) => {
+                tracing::debug!(%base, error = %e, "failed to measure throughput");
+                None
+            }
+        };
+
+        return Ok(Probe {
+            latency,
+            capabilities: Capabilities {
+                max_quality: Some(manifest.audio_quality.unwrap_or(tier)),
+                previews,
+                manifest: Some(manifest.manifest_mime_type),
+                throughput,
+            },
+        });
+    }
+
+    Ok(Probe {
+        latency: latency.expect("the ladder is never empty, so a request was sent"),
+        capabilities: Capabilities {
+            max_quality: None,
+            previews,
+            manifest: None,
+            throughput: None,
+        },
+    })
+}
+
+/// times the first media segment, or the first few hundred kilobytes of a whole file
+async fn throughput(
+    client: &reqwest::Client,
+    timeouts: &TimeoutPolicy,
+    manifest: &TrackManifest,
+) -> Result<f64, MonochromeError> {
+    let media = timeouts.media;
+    let start = Instant::now();
+
+    let received = match manifest.parse()? {
+        Manifest::Dash(plan) => {
+            let segment = plan
+                .segments
+                .first()
+                .or(plan.initialization.as_ref())
+                .ok_or(MonochromeError::ManifestDecode)?;
+            fetch_segment(client, media, segment).await?.len() as u64
+        }
+        Manifest::Bts(bts) => {
+            let url = bts.urls.first().ok_or(MonochromeError::ManifestDecode)?;
+            let request = client
+                .get(url.clone())
+                .header(header::RANGE, format!("bytes=0-{}", THROUGHPUT_BYTES - 1));
+            let res = media.send(request).await?;
+            let res = media.check(res, RequestKind::Probe).await?;
+
+            // a server that ignores the range would send the whole track
+            let mut body = std::pin::pin!(media.body(res));
+            let mut received = 0;
+            while received < THROUGHPUT_BYTES
+                && let Some(chunk) = body.next().await
+            {
+                received += chunk?.len() as u64;
+            }
+            received
+        }
+    };
+
+    Ok(received as f64 / start.elapsed().as_secs_f64().max(f64::EPSILON))
+}
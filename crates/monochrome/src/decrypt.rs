@@ -0,0 +1,104 @@
+use aes::{
+    Aes128, Aes256,
+    cipher::{BlockDecryptMut, KeyIvInit, StreamCipher, block_padding::NoPadding},
+};
+use base64::{Engine, prelude::BASE64_STANDARD};
+use bytes::{Bytes, BytesMut};
+use futures::{Stream, StreamExt};
+
+use crate::MonochromeError;
+
+/// the key every `OLD_AES` key id is wrapped with, baked into the official clients
+const MASTER_KEY: &str = "UIlTTEMmmLfGowo/UC60x2H45W6MdGgTRfo/umg4754=";
+
+pub(crate) type Aes128Ctr = ctr::Ctr128BE<Aes128>;
+type Aes256CbcDec = cbc::Decryptor<Aes256>;
+
+/// the key and nonce for one track, unwrapped from the manifest's `keyId`
+#[derive(Debug, Clone)]
+pub(crate) struct TrackKey {
+    key: [u8; 16],
+    nonce: [u8; 8],
+}
+
+impl TrackKey {
+    /// a key id is a 16 byte iv followed by the track key and nonce, aes-256-cbc encrypted with
+    /// [`MASTER_KEY`]
+    pub(crate) fn from_key_id(key_id: &str) -> Result<Self, MonochromeError> {
+        let master = BASE64_STANDARD.decode(MASTER_KEY)?;
+        let mut token = BASE64_STANDARD.decode(key_id)?;
+
+        if token.len() < 16 + 32 || (token.len() - 16) % 16 != 0 {
+            return Err(MonochromeError::KeyDecrypt(format!(
+                "key id is {} bytes",
+                token.len()
+            )));
+        }
+
+        let (iv, wrapped) = token.split_at_mut(16);
+        let decrypted = Aes256CbcDec::new_from_slices(&master, iv)
+            .map_err(|e| MonochromeError::KeyDecrypt(e.to_string()))?
+            .decrypt_padded_mut::<NoPadding>(wrapped)
+            .map_err(|e| MonochromeError::KeyDecrypt(e.to_string()))?;
+
+        let mut key = [0; 16];
+        let mut nonce = [0; 8];
+        key.copy_from_slice(&decrypted[..16]);
+        nonce.copy_from_slice(&decrypted[16..24]);
+
+        Ok(Self { key, nonce })
+    }
+
+    /// the other direction of [`TrackKey::from_key_id`], for serving encrypted tracks
+    #[cfg(feature = "mock")]
+    pub(crate) fn to_key_id(&self, iv: [u8; 16]) -> String {
+        use aes::cipher::BlockEncryptMut;
+
+        let master = BASE64_STANDARD.decode(MASTER_KEY).unwrap();
+        let mut plain = [0; 32];
+        plain[..16].copy_from_slice(&self.key);
+        plain[16..24].copy_from_slice(&self.nonce);
+
+        let wrapped = cbc::Encryptor::<Aes256>::new_from_slices(&master, &iv)
+            .unwrap()
+            .encrypt_padded_mut::<NoPadding>(&mut plain, 32)
+            .unwrap();
+
+        BASE64_STANDARD.encode([iv.as_slice(), wrapped].concat())
+    }
+
+    #[cfg(feature = "mock")]
+    pub(crate) fn new(key: [u8; 16], nonce: [u8; 8]) -> Self {
+        Self { key, nonce }
+    }
+
+    /// aes-128-ctr, with the nonce in the high half of the counter block
+    pub(crate) fn cipher(&self) -> Aes128Ctr {
+        let mut iv = [0; 16];
+        iv[..8].copy_from_slice(&self.nonce);
+        Aes128Ctr::new(&self.key.into(), &iv.into())
+    }
+}
+
+/// decrypts the stream as it goes. ctr is a stream cipher, so chunks can be any size as long as
+/// they arrive in order, which holds across resumes too since they continue the same stream
+pub(crate) fn decrypt<S>(
+    stream: S,
+    key: Option<TrackKey>,
+) -> impl Stream<Item = Result<Bytes, MonochromeError>>
+where
+    S: Stream<Item = Result<Bytes, MonochromeError>>,
+{
+    let mut cipher = key.as_ref().map(TrackKey::cipher);
+
+    stream.map(move |chunk| {
+        let chunk = chunk?;
+        let Some(cipher) = &mut cipher else {
+            return Ok(chunk);
+        };
+
+        let mut buf = BytesMut::from(chunk);
+        cipher.apply_keystream(&mut buf);
+        Ok(buf.freeze())
+    })
+}
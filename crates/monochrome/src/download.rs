@@ -0,0 +1,490 @@
+use crate::{
+    MonochromeError, RequestKind,
+    endpoint::{Endpoint, FetchKind},
+    id::TrackId,
+    manifest::{Manifest, StreamInfo},
+    mpd::{Segment, SegmentPlan},
+    quality::AudioQuality,
+    timeout::Timeouts,
+    track::TrackManifest,
+};
+use async_stream::try_stream;
+use bytes::Bytes;
+use futures::{Stream, StreamExt, stream};
+use reqwest::{StatusCode, header};
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::{
+        Arc,
+        atomic::{AtomicU32, Ordering},
+    },
+    task::{Context, Poll},
+    time::Duration,
+};
+use tokio::{
+    sync::OnceCell,
+    task::{JoinError, JoinHandle},
+};
+
+pub const DEFAULT_PREFETCH_WINDOW: usize = 8;
+
+/// how hard to try for each dash segment before giving up on the whole stream
+#[derive(Debug, Clone)]
+pub struct SegmentRetryPolicy {
+    /// attempts per source, so the original cdn url and each failover mirror get this many each
+    pub attempts: u32,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+    /// whether to fetch the manifest from other streaming mirrors once the original url is
+    /// exhausted
+    pub failover: bool,
+}
+
+impl Default for SegmentRetryPolicy {
+    fn default() -> Self {
+        Self {
+            attempts: 4,
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(8),
+            failover: true,
+        }
+    }
+}
+
+/// counters for a single track download, updated live as segments come in
+#[derive(Debug, Default)]
+pub struct DownloadStats {
+    segment_retries: AtomicU32,
+    failovers: AtomicU32,
+    resumes: AtomicU32,
+}
+
+impl DownloadStats {
+    /// how many segment requests failed and were retried
+    pub fn segment_retries(&self) -> u32 {
+        self.segment_retries.load(Ordering::Relaxed)
+    }
+
+    /// how many segments had to be fetched through another streaming mirror
+    pub fn failovers(&self) -> u32 {
+        self.failovers.load(Ordering::Relaxed)
+    }
+
+    /// how many times a single file download was resumed with a range request
+    pub fn resumes(&self) -> u32 {
+        self.resumes.load(Ordering::Relaxed)
+    }
+}
+
+/// the byte stream of a track, along with the stats of the download producing it and what the
+/// manifest said is in it
+pub struct TrackStream<S> {
+    inner: S,
+    stats: Arc<DownloadStats>,
+    info: StreamInfo,
+}
+
+impl<S> TrackStream<S> {
+    pub(crate) fn new(inner: S, stats: Arc<DownloadStats>, info: StreamInfo) -> Self {
+        Self { inner, stats, info }
+    }
+
+    pub fn stats(&self) -> Arc<DownloadStats> {
+        self.stats.clone()
+    }
+
+    pub fn info(&self) -> &StreamInfo {
+        &self.info
+    }
+}
+
+impl<S: Stream<Item = Result<Bytes, MonochromeError>> + Unpin> Stream for TrackStream<S> {
+    type Item = Result<Bytes, MonochromeError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        Pin::new(&mut self.inner).poll_next(cx)
+    }
+}
+
+/// a spawned task that's aborted when dropped, so segments that are still in flight stop as soon
+/// as the stream they belong to goes away
+pub(crate) struct AbortOnDrop<T>(JoinHandle<T>);
+
+impl<T> AbortOnDrop<T> {
+    pub(crate) fn spawn<F>(future: F) -> Self
+    where
+        F: Future<Output = T> + Send + 'static,
+        T: Send + 'static,
+    {
+        Self(tokio::spawn(future))
+    }
+}
+
+impl<T> Future for AbortOnDrop<T> {
+    type Output = Result<T, JoinError>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        Pin::new(&mut self.0).poll(cx)
+    }
+}
+
+impl<T> Drop for AbortOnDrop<T> {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
+
+/// which part of a segment plan to fetch
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum SegmentIndex {
+    Initialization,
+    Media(usize),
+}
+
+impl SegmentIndex {
+    fn get(self, plan: &SegmentPlan) -> Option<&Segment> {
+        match self {
+            SegmentIndex::Initialization => plan.initialization.as_ref(),
+            SegmentIndex::Media(idx) => plan.segments.get(idx),
+        }
+    }
+}
+
+/// segment plans for the same track from the other streaming mirrors. they're only fetched the
+/// first time a segment runs out of retries on its original url, or, just enough of them, up
+/// front when segments are being spread across mirrors
+pub(crate) struct Failover {
+    endpoint: Endpoint,
+    track_id: TrackId,
+    quality: Option<AudioQuality>,
+    original: SegmentPlan,
+    plans: OnceCell<Vec<SegmentPlan>>,
+}
+
+impl Failover {
+    pub(crate) fn new(endpoint: Endpoint, manifest: &TrackManifest, plan: &SegmentPlan) -> Self {
+        Self {
+            endpoint,
+            track_id: manifest.track_id,
+            quality: manifest.audio_quality,
+            original: plan.clone(),
+            plans: OnceCell::new(),
+        }
+    }
+
+    /// the plans from other mirrors, in the order they should be tried
+    pub(crate) async fn plans(&self) -> &[SegmentPlan] {
+        self.plans
+            .get_or_init(|| async {
+                let mut plans = Vec::new();
+                for mirror in self.mirrors().await {
+                    let plan = self.plan_from(&mirror).await;
+                    plans.extend(self.usable(&mirror, plan));
+                }
+                plans
+            })
+            .await
+    }
+
+    /// the first `n` plans from other mirrors, fetched `n` at a time and without asking the
+    /// mirrors after them
+    pub(crate) async fn spread(&self, n: usize) -> Vec<SegmentPlan> {
+        stream::iter(self.mirrors().await)
+            .map(async |mirror| {
+                let plan = self.plan_from(&mirror).await;
+                self.usable(&mirror, plan)
+            })
+            .buffered(n)
+            .filter_map(std::future::ready)
+            .take(n)
+            .collect()
+            .await
+    }
+
+    async fn mirrors(&self) -> Vec<reqwest::Url> {
+        let kind = self.quality.map_or(FetchKind::Streaming, FetchKind::Track);
+        self.endpoint.candidates(kind).await
+    }
+
+    fn usable(
+        &self,
+        mirror: &reqwest::Url,
+        plan: Result<Option<SegmentPlan>, MonochromeError>,
+    ) -> Option<SegmentPlan> {
+        match plan {
+            // the same urls again, most likely from the mirror that served the original manifest
+            Ok(Some(plan)) if plan.segments.first() == self.original.segments.first() => None,
+            // a different representation would splice mismatched audio together
+            Ok(Some(plan))
+                if plan.representation_id == self.original.representation_id
+                    && plan.segments.len() == self.original.segments.len() =>
+            {
+                Some(plan)
+            }
+            Ok(None) => {
+                tracing::debug!(%mirror, "failover mirror serves the track as a single file");
+                None
+            }
+            Ok(Some(_)) => {
+                tracing::debug!(%mirror, "failover mirror serves a different representation");
+                None
+            }
+            Err(e) => {
+                tracing::debug!(%mirror, error = %e, "failed to fetch failover manifest");
+                None
+            }
+        }
+    }
+
+    async fn plan_from(
+        &self,
+        mirror: &reqwest::Url,
+    ) -> Result<Option<SegmentPlan>, MonochromeError> {
+        let id = self.track_id.to_string();
+        let manifest: TrackManifest = match self.quality {
+            Some(quality) => {
+                self.endpoint
+                    .fetch_from(
+                        mirror,
+                        "track",
+                        FetchKind::Streaming,
+                        [("id", id.as_str()), ("quality", quality.as_str())],
+                    )
+                    .await?
+            }
+            None => {
+                self.endpoint
+                    .fetch_from(mirror, "track", FetchKind::Streaming, [("id", id.as_str())])
+                    .await?
+            }
+        };
+
+        // a mirror answering with a whole-file manifest has no segments to borrow
+        match manifest.parse()? {
+            Manifest::Dash(plan) => Ok(Some(plan)),
+            Manifest::Bts(_) => Ok(None),
+        }
+    }
+}
+
+/// fetches one segment, retrying with backoff on the url it was assigned and then on every other
+/// plan before giving up
+pub(crate) async fn fetch_with_failover(
+    client: reqwest::Client,
+    segment: Segment,
+    index: SegmentIndex,
+    failover: &Failover,
+    policy: &SegmentRetryPolicy,
+    stats: &DownloadStats,
+) -> Result<Bytes, MonochromeError> {
+    let timeouts = failover.endpoint.timeouts().media;
+    let err = match fetch_with_retry(&client, timeouts, &segment, policy, stats).await {
+        Ok(bytes) => return Ok(bytes),
+        Err(e) if !policy.failover => return Err(e),
+        Err(e) => e,
+    };
+
+    let plans = failover.plans().await;
+    for plan in std::iter::once(&failover.original).chain(plans) {
+        let Some(alternative) = index.get(plan) else {
+            continue;
+        };
+
+        if alternative.url == segment.url {
+            continue;
+        }
+
+        tracing::warn!(?index, url = %alternative.url, "segment failed, failing over to another mirror");
+        stats.failovers.fetch_add(1, Ordering::Relaxed);
+
+        if let Ok(bytes) = fetch_with_retry(&client, timeouts, alternative, policy, stats).await {
+            return Ok(bytes);
+        }
+    }
+
+    Err(err)
+}
+
+async fn fetch_with_retry(
+    client: &reqwest::Client,
+    timeouts: Timeouts,
+    segment: &Segment,
+    policy: &SegmentRetryPolicy,
+    stats: &DownloadStats,
+) -> Result<Bytes, MonochromeError> {
+    let mut backoff = policy.initial_backoff;
+    let mut attempt = 1;
+
+    loop {
+        match fetch_segment(client, timeouts, segment).await {
+            Ok(bytes) => return Ok(bytes),
+            Err(e) if attempt < policy.attempts => {
+                tracing::debug!(url = %segment.url, attempt, error = %e, "segment failed, retrying");
+                stats.segment_retries.fetch_add(1, Ordering::Relaxed);
+
+                tokio::time::sleep(backoff).await;
+                backoff = (backoff * 2).min(policy.max_backoff);
+                attempt += 1;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+pub(crate) async fn fetch_segment(
+    client: &reqwest::Client,
+    timeouts: Timeouts,
+    segment: &Segment,
+) -> Result<Bytes, MonochromeError> {
+    let mut req = client.get(segment.url.clone());
+    if let Some(range) = segment.range {
+        req = req.header(reqwest::header::RANGE, range.header_value());
+    }
+
+    let res = timeouts.send(req).await?;
+    let res = timeouts.check(res, RequestKind::Media).await?;
+    timeouts.bytes(res).await
+}
+
+/// streams a single file, picking up from the last received byte with a range request whenever
+/// the connection drops or the body ends short of its content length. `first` is the already
+/// checked response to the initial request
+pub(crate) fn resumable(
+    client: reqwest::Client,
+    timeouts: Timeouts,
+    url: String,
+    first: reqwest::Response,
+    policy: SegmentRetryPolicy,
+    stats: Arc<DownloadStats>,
+) -> impl Stream<Item = Result<Bytes, MonochromeError>> {
+    try_stream! {
+        let mut total = first.content_length();
+        let mut response = Some(first);
+        let mut offset: u64 = 0;
+        let mut attempt = 1;
+        let mut backoff = policy.initial_backoff;
+
+        loop {
+            let res = match response.take() {
+                Some(res) => Ok((res, 0)),
+                None => resume_from(&client, timeouts, &url, offset).await,
+            };
+
+            let err = match res {
+                Ok((res, mut skip)) => {
+                    if total.is_none() {
+                        total = content_range_total(&res);
+                    }
+
+                    let mut body = std::pin::pin!(timeouts.body(res));
+                    let mut failed = None;
+
+                    while let Some(chunk) = body.next().await {
+                        let mut chunk = match chunk {
+                            Ok(chunk) => chunk,
+                            Err(e) => {
+                                failed = Some(e);
+                                break;
+                            }
+                        };
+
+                        if skip > 0 {
+                            let n = skip.min(chunk.len() as u64);
+                            chunk = chunk.slice(n as usize..);
+                            skip -= n;
+                        }
+
+                        if chunk.is_empty() {
+                            continue;
+                        }
+
+                        offset += chunk.len() as u64;
+                        attempt = 1;
+                        backoff = policy.initial_backoff;
+                        yield chunk;
+                    }
+
+                    match (failed, total) {
+                        (Some(e), _) => e,
+                        (None, Some(expected)) if offset < expected => MonochromeError::Truncated {
+                            received: offset,
+                            expected,
+                        },
+                        (None, _) => break,
+                    }
+                }
+                Err(e) => e,
+            };
+
+            tracing::warn!(%url, offset, attempt, error = %err, "download interrupted");
+            if attempt >= policy.attempts {
+                Err(err)?;
+            }
+
+            stats.resumes.fetch_add(1, Ordering::Relaxed);
+
+            tokio::time::sleep(backoff).await;
+            backoff = (backoff * 2).min(policy.max_backoff);
+            attempt += 1;
+        }
+    }
+}
+
+/// requests the rest of the file from `offset`, along with how many bytes at the start of the
+/// response are ones we already have
+async fn resume_from(
+    client: &reqwest::Client,
+    timeouts: Timeouts,
+    url: &str,
+    offset: u64,
+) -> Result<(reqwest::Response, u64), MonochromeError> {
+    let req = client
+        .get(url)
+        .header(header::RANGE, format!("bytes={offset}-"));
+
+    let res = timeouts.send(req).await?;
+    let res = timeouts.check(res, RequestKind::Media).await?;
+
+    let skip = match (res.status(), content_range_start(&res)) {
+        // a range that starts early is dropped up to where we are, the same as a full body
+        (StatusCode::PARTIAL_CONTENT, Some(start)) if start <= offset => offset - start,
+        // one that starts late would leave a hole
+        (StatusCode::PARTIAL_CONTENT, Some(start)) => {
+            return Err(MonochromeError::RangeMismatch {
+                requested: offset,
+                start,
+            });
+        }
+        (StatusCode::PARTIAL_CONTENT, None) => 0,
+        // a server that ignores the range sends everything again
+        _ => offset,
+    };
+
+    Ok((res, skip))
+}
+
+/// where a `Content-Range: bytes 100-199/200` header says the body starts
+fn content_range_start(res: &reqwest::Response) -> Option<u64> {
+    res.headers()
+        .get(header::CONTENT_RANGE)?
+        .to_str()
+        .ok()?
+        .strip_prefix("bytes ")?
+        .split_once('-')?
+        .0
+        .parse()
+        .ok()
+}
+
+/// the full size from a `Content-Range: bytes 100-199/200` header
+fn content_range_total(res: &reqwest::Response) -> Option<u64> {
+    res.headers()
+        .get(header::CONTENT_RANGE)?
+        .to_str()
+        .ok()?
+        .rsplit_once('/')?
+        .1
+        .parse()
+        .ok()
+}
@@ -1,96 +1,221 @@
-use std::{sync::Arc, time::Duration};
+use std::{
+    collections::HashMap,
+    sync::{
+        Arc,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::Instant,
+};
 
 use crate::{
-    Monochrome, MonochromeError, error::MonochromeManifestError, response::MonochromeResponse,
-    track::TrackManifest,
+    Monochrome, MonochromeError, RequestKind,
+    capability::{self, Capabilities, PROBE_TRACK},
+    fixture::{self, FixtureKey, FixtureMode},
+    health::{HealthPolicy, HealthTracker, MirrorHealth},
+    quality::AudioQuality,
+    ratelimit::{self, RateLimitPolicy, RateLimiter},
+    response::MonochromeResponse,
+    source::{EndpointSource, MirrorList, UptimeSource},
+    timeout::{TimeoutPolicy, Timeouts},
 };
+use bytes::Bytes;
 use chrono::Utc;
-use reqwest::{Response, Url};
-use serde::Deserialize;
+use reqwest::{RequestBuilder, Response, Url};
 use thiserror::Error;
-use tokio::sync::RwLock;
-
-const UPTIME_URL: &str = "https://tidal-uptime.jiffy-puffs-1j.workers.dev";
+use tokio::{
+    sync::{Mutex, RwLock},
+    task::JoinHandle,
+};
 
 #[derive(Debug, Error)]
 pub enum ScanError {
     #[error("failed to fetch url: {0:?}")]
     Fetch(#[from] reqwest::Error),
+
+    #[error("failed to fetch uptime: {0}")]
+    Uptime(#[from] MonochromeError),
+
+    #[error("failed to read or write the mirror cache: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("failed to parse the mirror cache: {0}")]
+    Json(#[from] serde_json::Error),
+
+    #[error("no endpoint source returned any mirrors")]
+    NoMirrors,
 }
 
 #[derive(Debug, Clone)]
 pub struct Endpoint {
     // TODO: would prefer if these weren't Arc<RwLock<T>> but this is the easiest way for now
-    preferred_api: Arc<RwLock<Url>>,
-    preferred_streaming: Arc<RwLock<Url>>,
+    // every mirror that passed the last scan, best first. requests are spread across them by
+    // `health`
+    api_mirrors: Arc<RwLock<Vec<Url>>>,
+    streaming_mirrors: Arc<RwLock<Vec<Url>>>,
+    // what each streaming mirror served when it was last probed. mirrors that haven't been
+    // probed yet are assumed to serve everything
+    capabilities: Arc<RwLock<HashMap<Url, Capabilities>>>,
+    // held for the length of a scan, so requests that all find every mirror failing wait for one
+    // rescan instead of each starting their own
+    scanning: Arc<Mutex<()>>,
+    // how many scans have finished, to tell whether one ran while a request was failing
+    scans: Arc<AtomicU64>,
+    health: Arc<HealthTracker>,
+    limiter: Arc<RateLimiter>,
+    sources: Arc<[Box<dyn EndpointSource>]>,
     client: reqwest::Client,
+    timeouts: TimeoutPolicy,
+    fixtures: Option<FixtureMode>,
+}
+
+impl Default for Endpoint {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Endpoint {
     pub fn new() -> Self {
+        let timeouts = TimeoutPolicy::default();
+        let default: Url = "https://triton.squid.wtf".parse().unwrap();
         Self {
-            preferred_api: Arc::new(RwLock::new("https://triton.squid.wtf".parse().unwrap())),
-            preferred_streaming: Arc::new(RwLock::new("https://triton.squid.wtf".parse().unwrap())),
-            client: reqwest::Client::new(),
+            api_mirrors: Arc::new(RwLock::new(vec![default.clone()])),
+            streaming_mirrors: Arc::new(RwLock::new(vec![default])),
+            capabilities: Arc::new(RwLock::new(HashMap::new())),
+            scanning: Arc::new(Mutex::new(())),
+            scans: Arc::new(AtomicU64::new(0)),
+            health: Arc::new(HealthTracker::new(HealthPolicy::default())),
+            limiter: Arc::new(RateLimiter::new(RateLimitPolicy::default())),
+            sources: Arc::new([Box::new(UptimeSource::default()) as Box<dyn EndpointSource>]),
+            client: build_client(&timeouts),
+            timeouts,
+            fixtures: None,
         }
     }
 
+    /// replaces where scans look for mirrors. every source is asked and their mirrors are
+    /// measured together, so one being down doesn't matter as long as another answers
+    pub fn with_sources(mut self, sources: Vec<Box<dyn EndpointSource>>) -> Self {
+        self.sources = sources.into();
+        self
+    }
+
+    /// the mirrors requests go to before the first scan finishes, or when it fails. an empty
+    /// list keeps the default for that kind
+    pub fn with_mirrors(mut self, mirrors: MirrorList) -> Self {
+        if !mirrors.api.is_empty() {
+            self.api_mirrors = Arc::new(RwLock::new(mirrors.api));
+        }
+
+        if !mirrors.streaming.is_empty() {
+            self.streaming_mirrors = Arc::new(RwLock::new(mirrors.streaming));
+        }
+
+        self
+    }
+
+    /// replaces the per-mirror request budgets
+    pub fn with_rate_limits(mut self, policy: RateLimitPolicy) -> Self {
+        self.limiter = Arc::new(RateLimiter::new(policy));
+        self
+    }
+
+    /// replaces the circuit breaker policy. health recorded so far is thrown away
+    pub fn with_health_policy(mut self, policy: HealthPolicy) -> Self {
+        self.health = Arc::new(HealthTracker::new(policy));
+        self
+    }
+
+    /// replaces the timeout policy, rebuilding the http client so the connect timeout applies
+    pub fn with_timeouts(mut self, timeouts: TimeoutPolicy) -> Self {
+        self.client = build_client(&timeouts);
+        self.timeouts = timeouts;
+        self
+    }
+
+    /// records api responses to a directory, or answers from one instead of the mirrors
+    pub fn with_fixtures(mut self, mode: FixtureMode) -> Self {
+        self.fixtures = Some(mode);
+        self
+    }
+
+    fn is_replaying(&self) -> bool {
+        matches!(self.fixtures, Some(FixtureMode::Replay(_)))
+    }
+
     pub async fn scan(&self) -> Result<(), ScanError> {
-        let uptime: UptimeResponse = self.client.get(UPTIME_URL).send().await?.json().await?;
+        if self.is_replaying() {
+            tracing::debug!("replaying fixtures, skipping scan");
+            return Ok(());
+        }
+
+        let _scanning = self.scanning.lock().await;
+        let res = self.scan_mirrors().await;
+        self.scans.fetch_add(1, Ordering::AcqRel);
+        res
+    }
+
+    /// scans unless another scan finished after `seen` was read from `scans`, in which case the
+    /// mirror list is already as fresh as a scan would make it
+    async fn rescan(&self, seen: u64) -> Result<(), ScanError> {
+        let _scanning = self.scanning.lock().await;
+        if self.scans.load(Ordering::Acquire) != seen {
+            tracing::debug!("mirrors were rescanned while waiting, skipping scan");
+            return Ok(());
+        }
+
+        let res = self.scan_mirrors().await;
+        self.scans.fetch_add(1, Ordering::AcqRel);
+        res
+    }
+
+    async fn scan_mirrors(&self) -> Result<(), ScanError> {
+        let metadata = self.timeouts.metadata;
+        let mut found = MirrorList::default();
+
+        for source in self.sources.iter() {
+            match source.mirrors(&self.client, metadata).await {
+                Ok(mirrors) => {
+                    tracing::debug!(
+                        source = source.name(),
+                        api = mirrors.api.len(),
+                        streaming = mirrors.streaming.len(),
+                        "found mirrors"
+                    );
+                    found.merge(mirrors);
+                }
+                Err(e) => {
+                    tracing::warn!(source = source.name(), error = %e, "failed to get mirrors from source")
+                }
+            }
+        }
+
+        if found.is_empty() {
+            return Err(ScanError::NoMirrors);
+        }
 
-        let api_futs = uptime
+        let api_futs = found
             .api
             .into_iter()
-            .map(|a| ApiMeasureInfo {
-                url: a.url.clone(),
-                test_url: {
-                    let mut url = a.url.clone();
-                    url.set_path("/album");
-                    url.set_query(Some("id=109485854"));
-                    url
-                },
-                kind: MeasureKind::Api,
-            })
+            .map(|url| ApiMeasureInfo::new(url, MeasureKind::Api))
             .map(|api| {
                 let client = self.client.clone();
-                (api.url.clone(), fut(api, client, async |_| Ok(())))
+                let url = api.url.clone();
+                (url.clone(), async move {
+                    self.limiter.acquire(&url).await;
+                    fut(api, client, metadata, async |_| Ok(())).await
+                })
             });
 
-        let streaming_futs = uptime
-            .streaming
-            .into_iter()
-            .map(|a| ApiMeasureInfo {
-                url: a.url.clone(),
-                test_url: {
-                    let mut url = a.url.clone();
-                    url.set_path("/track");
-                    url.set_query(Some("id=109485855"));
-                    url
-                },
-                kind: MeasureKind::Streaming,
-            })
-            .map(|api| {
-                let client = self.client.clone();
-                (
-                    api.url.clone(),
-                    fut(api, client, async |res| {
-                        let data: MonochromeResponse<TrackManifest> = res.json().await?;
-                        let data = data.data;
-                        if data.asset_presentation != "PREVIEW" {
-                            return Err(MonochromeError::Manifest(
-                                MonochromeManifestError::Preview,
-                            ));
-                        }
-                        Ok(())
-                    }),
-                )
-            });
+        let streaming_futs = found.streaming.into_iter().map(|url| async move {
+            tracing::debug!(%url, "probing streaming endpoint");
+            let probe = capability::probe(&self.client, &self.timeouts, &self.limiter, &url).await;
+            (url, probe)
+        });
 
         let (api_measurements, streaming_measurements) = futures::future::join(
             futures::future::join_all(api_futs.map(|(url, fut)| async move { (url, fut.await) })),
-            futures::future::join_all(
-                streaming_futs.map(|(url, fut)| async move { (url, fut.await) }),
-            ),
+            futures::future::join_all(streaming_futs),
         )
         .await;
 
@@ -105,32 +230,130 @@ impl Endpoint {
             })
             .collect::<Vec<_>>();
 
-        let mut streaming_measurements = streaming_measurements
-            .into_iter()
-            .filter_map(|(u, m)| match m {
-                Ok(m) => Some(m),
+        let mut capabilities = HashMap::new();
+        let mut streaming_probes = Vec::new();
+        for (url, probe) in streaming_measurements {
+            match probe {
+                Ok(probe) => {
+                    tracing::debug!(
+                        %url,
+                        latency = ?probe.latency,
+                        max_quality = ?probe.capabilities.max_quality,
+                        manifest = ?probe.capabilities.manifest,
+                        throughput = ?probe.capabilities.throughput,
+                        "probed streaming endpoint"
+                    );
+                    capabilities.insert(url.clone(), probe.capabilities.clone());
+
+                    // a mirror that only serves previews can't satisfy any download
+                    if probe.capabilities.full_tracks() {
+                        streaming_probes.push((url, probe));
+                    } else {
+                        tracing::warn!(%url, "streaming endpoint only serves previews");
+                    }
+                }
                 Err(e) => {
-                    tracing::warn!(error = %e, url = %u, "failed to measure streaming endpoint");
-                    None
+                    tracing::warn!(error = %e, %url, "failed to probe streaming endpoint");
                 }
-            })
-            .collect::<Vec<_>>();
+            }
+        }
 
         api_measurements.sort_by_key(|m| m.latency);
 
-        streaming_measurements.sort_by_key(|m| m.latency);
+        // best tier first, then whoever moved the most audio. a quick first response says little
+        // about how fast segments will come
+        streaming_probes.sort_by(|(_, a), (_, b)| {
+            let (a, b) = (&a.capabilities, &b.capabilities);
+            quality_rank(b.max_quality)
+                .cmp(&quality_rank(a.max_quality))
+                .then(
+                    b.throughput
+                        .unwrap_or(0.0)
+                        .total_cmp(&a.throughput.unwrap_or(0.0)),
+                )
+        });
+
+        for m in &api_measurements {
+            self.health.record_success(&m.url, m.latency.to_std().ok());
+        }
+
+        for (url, probe) in &streaming_probes {
+            self.health.record_success(url, Some(probe.latency));
+            if let Some(throughput) = probe.capabilities.throughput {
+                self.health.record_throughput(url, throughput);
+            }
+        }
+
+        let healthy = MirrorList {
+            api: api_measurements.into_iter().map(|m| m.url).collect(),
+            streaming: streaming_probes.into_iter().map(|(url, _)| url).collect(),
+        };
+
+        self.capabilities.write().await.extend(capabilities);
 
-        if let Some(best) = api_measurements.into_iter().next() {
-            *self.preferred_api.write().await = best.url;
+        // an empty scan would leave nothing to send requests to, so keep the old pool instead
+        if !healthy.api.is_empty() {
+            *self.api_mirrors.write().await = healthy.api.clone();
         }
 
-        if let Some(best) = streaming_measurements.into_iter().next() {
-            *self.preferred_streaming.write().await = best.url;
+        if !healthy.streaming.is_empty() {
+            *self.streaming_mirrors.write().await = healthy.streaming.clone();
+        }
+
+        if !healthy.is_empty() {
+            for source in self.sources.iter() {
+                if let Err(e) = source.remember(&healthy).await {
+                    tracing::warn!(source = source.name(), error = %e, "failed to save healthy mirrors");
+                }
+            }
         }
 
         Ok(())
     }
 
+    /// probes every mirror whose circuit is half open, closing it again if it responds
+    pub async fn reprobe(&self) {
+        let streaming = self.streaming_mirrors().await;
+
+        for url in self.health.due_for_probe() {
+            let kind = if streaming.contains(&url) {
+                MeasureKind::Streaming
+            } else {
+                MeasureKind::Api
+            };
+
+            let info = ApiMeasureInfo::new(url.clone(), kind);
+            self.limiter.acquire(&url).await;
+            match fut(
+                info,
+                self.client.clone(),
+                self.timeouts.metadata,
+                async |_| Ok(()),
+            )
+            .await
+            {
+                Ok(m) => self.health.record_success(&url, m.latency.to_std().ok()),
+                Err(e) => {
+                    tracing::debug!(%url, error = %e, "mirror failed its probe");
+                    self.health.record_failure(&url);
+                }
+            }
+        }
+    }
+
+    /// re-probes demoted mirrors on the health policy's interval for as long as the task lives
+    pub fn spawn_reprobe(&self) -> JoinHandle<()> {
+        let endpoint = self.clone();
+        let interval = self.health.policy().reprobe_interval;
+
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(interval).await;
+                endpoint.reprobe().await;
+            }
+        })
+    }
+
     pub fn api(&self) -> Monochrome {
         Monochrome::new(self.clone())
     }
@@ -139,16 +362,84 @@ impl Endpoint {
         self.client.clone()
     }
 
+    pub fn timeouts(&self) -> &TimeoutPolicy {
+        &self.timeouts
+    }
+
+    /// the api mirror that currently gets the biggest share of requests
     pub async fn preferred_api(&self) -> Url {
-        self.preferred_api.read().await.clone()
+        let mirrors = self.api_mirrors.read().await;
+        self.health
+            .best(&mirrors)
+            .expect("the mirror pool is never empty")
     }
 
+    /// the streaming mirror that currently gets the biggest share of requests
     pub async fn preferred_streaming(&self) -> Url {
-        self.preferred_streaming.read().await.clone()
+        let mirrors = self.streaming_mirrors.read().await;
+        self.health
+            .best(&mirrors)
+            .expect("the mirror pool is never empty")
     }
 
-    pub(crate) async fn fetch<T, Q>(
+    /// every healthy api mirror from the last scan, fastest first
+    pub async fn api_mirrors(&self) -> Vec<Url> {
+        self.api_mirrors.read().await.clone()
+    }
+
+    /// every streaming mirror from the last scan that serves full tracks, best tier and highest
+    /// throughput first
+    pub async fn streaming_mirrors(&self) -> Vec<Url> {
+        self.streaming_mirrors.read().await.clone()
+    }
+
+    /// the health of every mirror a request has been sent to
+    pub fn health(&self) -> HashMap<Url, MirrorHealth> {
+        self.health.snapshot()
+    }
+
+    /// what every streaming mirror served when it was last probed
+    pub async fn capabilities(&self) -> HashMap<Url, Capabilities> {
+        self.capabilities.read().await.clone()
+    }
+
+    /// mirrors to try for a request, in the order to try them. the order is drawn fresh every
+    /// time so consecutive requests land on different mirrors
+    pub(crate) async fn candidates(&self, kind: FetchKind) -> Vec<Url> {
+        let mirrors = match kind {
+            FetchKind::Api => self.api_mirrors().await,
+            FetchKind::Streaming | FetchKind::Track(_) => self.streaming_mirrors().await,
+        };
+
+        // mirrors that told us to back off go last, they'd only make the request wait
+        let (blocked, mut ranked): (Vec<_>, Vec<_>) = self
+            .health
+            .rank(mirrors)
+            .into_iter()
+            .partition(|u| self.limiter.is_blocked(u));
+
+        ranked.extend(blocked);
+
+        let FetchKind::Track(quality) = kind else {
+            return ranked;
+        };
+
+        // a mirror that couldn't serve the tier when probed would answer with a preview or an
+        // error, so it's only asked once every mirror that could has failed
+        let capabilities = self.capabilities.read().await;
+        let (mut capable, incapable): (Vec<_>, Vec<_>) = ranked
+            .into_iter()
+            .partition(|u| capabilities.get(u).is_none_or(|c| c.serves(quality)));
+
+        capable.extend(incapable);
+        capable
+    }
+
+    /// fetches from a specific mirror, without rescanning or moving to another one on failure.
+    /// when replaying, the mirror is ignored and the response comes from the fixtures
+    pub(crate) async fn fetch_from<T, Q>(
         &self,
+        base: &Url,
         path: &str,
         kind: FetchKind,
         query: Q,
@@ -157,37 +448,129 @@ impl Endpoint {
         T: serde::de::DeserializeOwned,
         Q: serde::ser::Serialize,
     {
-        let url = match kind {
-            FetchKind::Api => self.preferred_api().await.join(path)?,
-            FetchKind::Streaming => self.preferred_streaming().await.join(path)?,
+        let request = self.client.get(base.join(path)?).query(&query).build()?;
+        let key = FixtureKey::new(path, request.url());
+
+        if let Some(FixtureMode::Replay(dir)) = &self.fixtures {
+            let value = fixture::replay(dir, &key).await?;
+            return Ok(serde_json::from_value::<MonochromeResponse<T>>(value)?.data);
+        }
+
+        self.limiter.acquire(base).await;
+
+        tracing::debug!(url = %request.url(), "fetching endpoint");
+
+        let start = Instant::now();
+        let res = self.request(request, kind.into()).await;
+        match &res {
+            Ok(_) => self.health.record_success(base, Some(start.elapsed())),
+            // the mirror is fine, we're just going too fast
+            Err(MonochromeError::RateLimited { retry_after }) => {
+                self.limiter.back_off(base, *retry_after)
+            }
+            Err(e) if e.is_mirror_fault() => self.health.record_failure(base),
+            Err(_) => {}
+        }
+
+        let body = res?;
+        let Some(FixtureMode::Record(dir)) = &self.fixtures else {
+            return Ok(serde_json::from_slice::<MonochromeResponse<T>>(&body)?.data);
         };
 
-        tracing::debug!(%url, "fetching endpoint");
+        let value: serde_json::Value = serde_json::from_slice(&body)?;
+        if let Err(e) = fixture::record(dir, &key, &value).await {
+            tracing::warn!(%key, error = %e, "failed to record fixture");
+        }
+
+        Ok(serde_json::from_value::<MonochromeResponse<T>>(value)?.data)
+    }
 
-        let response = self
-            .client
-            .get(url)
-            .timeout(Duration::from_secs(5))
-            .query(&query)
-            .send()
+    /// sends the request and reads the body of a 200, sorting every other status into the error
+    /// the caller needs to decide whether to move on to another mirror
+    async fn request(
+        &self,
+        request: reqwest::Request,
+        kind: RequestKind,
+    ) -> Result<Bytes, MonochromeError> {
+        let metadata = self.timeouts.metadata;
+        let response = metadata
+            .send(RequestBuilder::from_parts(self.client.clone(), request))
             .await?;
-        if response.status() != reqwest::StatusCode::OK {
-            // if it's 429, we need to find a diff endpoint
-            if response.status() == reqwest::StatusCode::TOO_MANY_REQUESTS {
-                tracing::warn!("endpoint returned 429, rescanning for new endpoints");
-                if let Err(e) = self.scan().await {
-                    tracing::error!(error = %e, "failed to rescan endpoints after 429");
-                } else {
-                    tracing::info!("rescan complete, retrying request");
-                    return Box::pin(self.fetch(path, kind, query)).await;
+
+        let status = response.status();
+        if status == reqwest::StatusCode::TOO_MANY_REQUESTS {
+            return Err(MonochromeError::RateLimited {
+                retry_after: ratelimit::retry_after(&response),
+            });
+        }
+
+        let response = metadata.check(response, kind).await?;
+        metadata.bytes(response).await
+    }
+
+    pub(crate) async fn fetch<T, Q>(
+        &self,
+        path: &str,
+        kind: FetchKind,
+        query: Q,
+    ) -> Result<T, MonochromeError>
+    where
+        T: serde::de::DeserializeOwned,
+        Q: serde::ser::Serialize,
+    {
+        // there's nothing to rank, and fixtures don't care which mirror a request went to
+        if self.is_replaying() {
+            let base = Url::parse("http://replay/")?;
+            return self.fetch_from(&base, path, kind, query).await;
+        }
+
+        let scans = self.scans.load(Ordering::Acquire);
+        match self.fetch_ranked(path, kind, &query).await {
+            // every mirror is rate limiting us, which a rescan can't fix. the next pass waits
+            // for their budgets to come back instead
+            Err(MonochromeError::RateLimited { .. }) => {
+                tracing::warn!("every mirror is rate limiting us, queueing the request");
+                self.fetch_ranked(path, kind, &query).await
+            }
+            // every mirror we know of is failing, so the list itself is probably stale
+            Err(err) if err.is_mirror_fault() => {
+                tracing::warn!(error = %err, "every mirror failed, rescanning for new endpoints");
+                if let Err(e) = self.rescan(scans).await {
+                    tracing::error!(error = %e, "failed to rescan endpoints");
+                    return Err(err);
                 }
+
+                tracing::info!("rescan complete, retrying request");
+                self.fetch_ranked(path, kind, &query).await
             }
+            res => res,
+        }
+    }
 
-            return Err(MonochromeError::Non200(response.text().await?));
+    /// tries each mirror in turn, moving on whenever one fails in a way that another might not
+    async fn fetch_ranked<T, Q>(
+        &self,
+        path: &str,
+        kind: FetchKind,
+        query: &Q,
+    ) -> Result<T, MonochromeError>
+    where
+        T: serde::de::DeserializeOwned,
+        Q: serde::ser::Serialize,
+    {
+        let mut last_err = None;
+
+        for base in self.candidates(kind).await {
+            match self.fetch_from(&base, path, kind, query).await {
+                Err(e) if e.is_mirror_fault() => {
+                    tracing::warn!(%base, error = %e, "mirror failed, trying the next one");
+                    last_err = Some(e);
+                }
+                res => return res,
+            }
         }
 
-        let data = response.json::<MonochromeResponse<T>>().await?;
-        Ok(data.data)
+        Err(last_err.expect("there's always at least one candidate mirror"))
     }
 }
 
@@ -195,17 +578,8 @@ impl Endpoint {
 pub enum FetchKind {
     Api,
     Streaming,
-}
-
-#[derive(Debug, Deserialize)]
-struct UptimeResponse {
-    api: Vec<Api>,
-    streaming: Vec<Api>,
-}
-
-#[derive(Debug, Deserialize)]
-struct Api {
-    url: Url,
+    /// a track manifest in this tier, preferably from a mirror that was seen serving it
+    Track(AudioQuality),
 }
 
 #[derive(Debug)]
@@ -215,6 +589,28 @@ struct ApiMeasureInfo {
     kind: MeasureKind,
 }
 
+impl ApiMeasureInfo {
+    fn new(url: Url, kind: MeasureKind) -> Self {
+        let mut test_url = url.clone();
+        match kind {
+            MeasureKind::Api => {
+                test_url.set_path("/album");
+                test_url.set_query(Some("id=109485854"));
+            }
+            MeasureKind::Streaming => {
+                test_url.set_path("/track");
+                test_url.set_query(Some(&format!("id={PROBE_TRACK}&quality=LOW")));
+            }
+        }
+
+        Self {
+            url,
+            test_url,
+            kind,
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 struct EndpointMeasurement {
     url: Url,
@@ -224,6 +620,7 @@ struct EndpointMeasurement {
 async fn fut<V>(
     api: ApiMeasureInfo,
     client: reqwest::Client,
+    timeouts: Timeouts,
     validate: V,
 ) -> Result<EndpointMeasurement, MonochromeError>
 where
@@ -231,16 +628,10 @@ where
 {
     let start = Utc::now();
     tracing::debug!(url = %api.url, kind = ?api.kind, "measuring endpoint");
-    let res = client
-        .get(api.test_url)
-        .timeout(Duration::from_secs(5))
-        .send()
-        .await?;
+    let res = timeouts.send(client.get(api.test_url)).await?;
 
     let latency = Utc::now() - start;
-    if res.status() != reqwest::StatusCode::OK {
-        return Err(MonochromeError::Non200(res.text().await?));
-    }
+    let res = timeouts.check(res, RequestKind::Probe).await?;
 
     validate(res).await?;
 
@@ -257,3 +648,15 @@ enum MeasureKind {
     Api,
     Streaming,
 }
+
+/// higher is better, `None` below everything
+fn quality_rank(quality: Option<AudioQuality>) -> usize {
+    quality.map_or(0, |q| q.ladder().count())
+}
+
+fn build_client(timeouts: &TimeoutPolicy) -> reqwest::Client {
+    reqwest::Client::builder()
+        .connect_timeout(timeouts.connect)
+        .build()
+        .expect("failed to build http client")
+}
@@ -1,12 +1,25 @@
 use thiserror::Error;
 
+use crate::endpoint::FetchKind;
+
 #[derive(Debug, Error)]
 pub enum MonochromeError {
     #[error("request failed: {0:?}")]
     Request(#[from] reqwest::Error),
 
-    #[error("non-200 response: {0}")]
-    Non200(String),
+    #[error("rate limited, retry after {retry_after:?}")]
+    RateLimited {
+        retry_after: Option<std::time::Duration>,
+    },
+
+    #[error("{kind} request to {endpoint} failed with {status}: {body}")]
+    Status {
+        status: reqwest::StatusCode,
+        /// the url without its query, which can hold tokens
+        endpoint: String,
+        kind: RequestKind,
+        body: String,
+    },
 
     #[error("base64 decode error: {0}")]
     Base64Decode(#[from] base64::DecodeError),
@@ -17,11 +30,151 @@ pub enum MonochromeError {
     #[error("failed to decode manifest")]
     ManifestDecode,
 
+    #[error("unsupported encryption: {0}")]
+    UnsupportedEncryption(String),
+
+    #[error("failed to decrypt track key: {0}")]
+    KeyDecrypt(String),
+
     #[error("manifest error: {0}")]
     Manifest(#[from] MonochromeManifestError),
 
     #[error("url parse error: {0}")]
     UrlParse(#[from] url::ParseError),
+
+    #[error("io error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("json decode error: {0}")]
+    Json(#[from] serde_json::Error),
+
+    #[error("no response within {0:?}")]
+    FirstByteTimeout(std::time::Duration),
+
+    #[error("no data received for {0:?}")]
+    ReadIdleTimeout(std::time::Duration),
+
+    #[error("stream ended early after {received} of {expected} bytes")]
+    Truncated { received: u64, expected: u64 },
+
+    #[error("asked to resume at byte {requested} but the response starts at {start}")]
+    RangeMismatch { requested: u64, start: u64 },
+
+    #[error("no size of artwork {0} is available")]
+    ArtworkUnavailable(uuid::Uuid),
+
+    #[error("no recorded response for {0}")]
+    MissingFixture(String),
+
+    #[error("segment task failed: {0}")]
+    Join(#[from] tokio::task::JoinError),
+
+    #[error("failed to acquire semaphore permit")]
+    Semaphore(#[from] tokio::sync::AcquireError),
+}
+
+/// what a request was for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RequestKind {
+    /// metadata from an api mirror
+    Api,
+    /// a track manifest from a streaming mirror
+    Streaming,
+    /// audio, either a dash segment or a whole file
+    Media,
+    /// covers and artist pictures from the resources server
+    Artwork,
+    /// checking whether a mirror works while scanning
+    Probe,
+}
+
+impl std::fmt::Display for RequestKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            RequestKind::Api => "api",
+            RequestKind::Streaming => "streaming",
+            RequestKind::Media => "media",
+            RequestKind::Artwork => "artwork",
+            RequestKind::Probe => "probe",
+        })
+    }
+}
+
+impl From<FetchKind> for RequestKind {
+    fn from(kind: FetchKind) -> Self {
+        match kind {
+            FetchKind::Api => RequestKind::Api,
+            FetchKind::Streaming | FetchKind::Track(_) => RequestKind::Streaming,
+        }
+    }
+}
+
+impl MonochromeError {
+    /// the status a server answered with, if that's what went wrong
+    pub fn status(&self) -> Option<reqwest::StatusCode> {
+        match self {
+            MonochromeError::Status { status, .. } => Some(*status),
+            MonochromeError::RateLimited { .. } => Some(reqwest::StatusCode::TOO_MANY_REQUESTS),
+            MonochromeError::Request(e) => e.status(),
+            _ => None,
+        }
+    }
+
+    /// whether the error says something about the mirror rather than the request, so the same
+    /// request could succeed on another one
+    pub(crate) fn is_mirror_fault(&self) -> bool {
+        match self {
+            MonochromeError::Request(e) => e.is_connect() || e.is_timeout() || e.is_body(),
+            MonochromeError::Status { status, .. } => status.is_server_error(),
+            MonochromeError::RateLimited { .. }
+            | MonochromeError::FirstByteTimeout(_)
+            | MonochromeError::ReadIdleTimeout(_) => true,
+            _ => false,
+        }
+    }
+
+    /// whether trying again later could help. a 404, a preview-only track or a manifest we can't
+    /// read will fail the same way every time, while timeouts, dropped connections and 5xx won't
+    /// necessarily
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            MonochromeError::Request(e) => is_transient_request(e),
+            MonochromeError::Status { status, .. } => is_transient(*status),
+            MonochromeError::Manifest(e) => e.is_retryable(),
+            MonochromeError::RateLimited { .. }
+            | MonochromeError::FirstByteTimeout(_)
+            | MonochromeError::ReadIdleTimeout(_)
+            | MonochromeError::Truncated { .. }
+            | MonochromeError::RangeMismatch { .. }
+            | MonochromeError::Io(_)
+            | MonochromeError::Join(_) => true,
+            MonochromeError::Base64Decode(_)
+            | MonochromeError::UnsupportedManifestMimeType(_)
+            | MonochromeError::ManifestDecode
+            | MonochromeError::UnsupportedEncryption(_)
+            | MonochromeError::KeyDecrypt(_)
+            | MonochromeError::UrlParse(_)
+            | MonochromeError::Json(_)
+            | MonochromeError::ArtworkUnavailable(_)
+            | MonochromeError::MissingFixture(_)
+            | MonochromeError::Semaphore(_) => false,
+        }
+    }
+}
+
+/// whether a request that failed with `status` could succeed if sent again
+pub fn is_transient(status: reqwest::StatusCode) -> bool {
+    status.is_server_error()
+        || status == reqwest::StatusCode::REQUEST_TIMEOUT
+        || status == reqwest::StatusCode::TOO_MANY_REQUESTS
+}
+
+/// [`is_transient`] for a failed request, which without a status means it never got an answer
+pub fn is_transient_request(e: &reqwest::Error) -> bool {
+    match e.status() {
+        Some(status) => is_transient(status),
+        None => e.is_connect() || e.is_timeout() || e.is_body() || e.is_request(),
+    }
 }
 
 #[derive(Debug, Error)]
@@ -29,7 +182,7 @@ pub enum MonochromeManifestError {
     #[error("failed to parse xml: {0}")]
     XmlParse(#[from] roxmltree::Error),
 
-    #[error("missing SegmentTemplate in MPD")]
+    #[error("missing SegmentTemplate, SegmentList or SegmentBase in MPD")]
     MissingSegmentTemplate,
 
     #[error("missing initialization template in SegmentTemplate")]
@@ -53,9 +206,15 @@ pub enum MonochromeManifestError {
     #[error("invalid MPD: no MPD node")]
     InvalidMpd,
 
-    #[error("negative repeat values are unsupported")]
+    #[error("negative repeat values are unsupported without a following S or a period duration")]
     NegativeRepeatUnsupported,
 
+    #[error("can't determine the segment count without a segment and period duration")]
+    MissingDuration,
+
+    #[error("invalid segment template: {0}")]
+    BadTemplate(String),
+
     #[error("failed to parse base URL")]
     BadBaseUrl,
 
@@ -68,3 +227,17 @@ pub enum MonochromeManifestError {
     #[error("preview tracks are unsupported")]
     Preview,
 }
+
+impl MonochromeManifestError {
+    /// only fetching and disk trouble can go away on its own, a manifest that doesn't parse
+    /// won't start parsing
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            MonochromeManifestError::FetchSegment(e) => {
+                e.status().is_none_or(is_transient) && !e.is_decode()
+            }
+            MonochromeManifestError::Fs(_) => true,
+            _ => false,
+        }
+    }
+}
@@ -0,0 +1,128 @@
+//! recording api responses to disk and replaying them later, so a payload that broke something in
+//! production can become a regression test
+
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Utc};
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+
+use crate::MonochromeError;
+
+// longest query that's used as a file name as is, anything longer is hashed
+const MAX_READABLE_QUERY: usize = 100;
+
+/// what [`Endpoint`](crate::endpoint::Endpoint) does with a fixture directory. only api calls and
+/// manifests go through it, track bytes and artwork are always fetched
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "mode", content = "dir", rename_all = "lowercase")]
+pub enum FixtureMode {
+    /// writes every successful response to the directory as it comes in
+    Record(PathBuf),
+    /// answers every request from the directory without touching the network. a request that
+    /// was never recorded fails with [`MonochromeError::MissingFixture`]
+    Replay(PathBuf),
+}
+
+/// a recorded response, as it's stored on disk
+#[derive(Debug, Serialize, Deserialize)]
+struct Fixture {
+    request: String,
+    recorded_at: DateTime<Utc>,
+    response: serde_json::Value,
+}
+
+/// identifies a request by its path and query, but not the mirror it went to, so recordings
+/// replay no matter which mirror served them
+#[derive(Debug, Clone)]
+pub(crate) struct FixtureKey {
+    path: String,
+    query: String,
+}
+
+impl FixtureKey {
+    /// `url` is the request url, which only contributes its query. parameters are sorted so the
+    /// order they were added in doesn't matter
+    pub(crate) fn new(path: &str, url: &Url) -> Self {
+        let mut pairs = url.query_pairs().into_owned().collect::<Vec<_>>();
+        pairs.sort();
+
+        let query = url::form_urlencoded::Serializer::new(String::new())
+            .extend_pairs(pairs)
+            .finish();
+
+        Self {
+            path: path.trim_matches('/').to_string(),
+            query,
+        }
+    }
+
+    fn file(&self, dir: &Path) -> PathBuf {
+        let readable = self.query.len() <= MAX_READABLE_QUERY
+            && self
+                .query
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || "=&%._+-".contains(c));
+
+        let name = if self.query.is_empty() {
+            "index".to_string()
+        } else if readable {
+            self.query.clone()
+        } else {
+            format!("{:016x}", fnv1a(&self.query))
+        };
+
+        dir.join(&self.path).join(format!("{name}.json"))
+    }
+}
+
+impl std::fmt::Display for FixtureKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}?{}", self.path, self.query)
+    }
+}
+
+pub(crate) async fn record(
+    dir: &Path,
+    key: &FixtureKey,
+    response: &serde_json::Value,
+) -> Result<(), MonochromeError> {
+    let path = key.file(dir);
+    if let Some(parent) = path.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+
+    let fixture = Fixture {
+        request: key.to_string(),
+        recorded_at: Utc::now(),
+        response: response.clone(),
+    };
+
+    tokio::fs::write(&path, serde_json::to_vec_pretty(&fixture)?).await?;
+    tracing::debug!(%key, path = %path.display(), "recorded fixture");
+    Ok(())
+}
+
+pub(crate) async fn replay(
+    dir: &Path,
+    key: &FixtureKey,
+) -> Result<serde_json::Value, MonochromeError> {
+    let path = key.file(dir);
+    let bytes = match tokio::fs::read(&path).await {
+        Ok(bytes) => bytes,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            return Err(MonochromeError::MissingFixture(key.to_string()));
+        }
+        Err(e) => return Err(e.into()),
+    };
+
+    let fixture: Fixture = serde_json::from_slice(&bytes)?;
+    Ok(fixture.response)
+}
+
+// file names have to stay the same across builds, which rules out the std hasher
+pub(crate) fn fnv1a(s: &str) -> u64 {
+    s.bytes().fold(0xcbf29ce484222325, |hash, b| {
+        (hash ^ b as u64).wrapping_mul(0x100000001b3)
+    })
+}
@@ -0,0 +1,245 @@
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use reqwest::Url;
+
+// how much weight the latest request gets in a mirror's error rate and latency
+const ERROR_RATE_ALPHA: f64 = 0.3;
+const LATENCY_ALPHA: f64 = 0.2;
+// what a mirror that's never answered a request is assumed to take
+const UNKNOWN_LATENCY: Duration = Duration::from_secs(1);
+// roughly a segment, what a mirror's throughput is weighed over
+const TRANSFER_BYTES: f64 = 256.0 * 1024.0;
+
+/// when to take a failing mirror out of rotation and when to try it again
+#[derive(Debug, Clone, Copy)]
+pub struct HealthPolicy {
+    /// consecutive failures before a mirror's circuit opens
+    pub failure_threshold: u32,
+    /// how long an open circuit keeps a mirror out of rotation before it gets probed again
+    pub cooldown: Duration,
+    /// the cooldown doubles every time a probe fails, up to this
+    pub max_cooldown: Duration,
+    /// how often the background task looks for mirrors to re-probe
+    pub reprobe_interval: Duration,
+}
+
+impl Default for HealthPolicy {
+    fn default() -> Self {
+        Self {
+            failure_threshold: 3,
+            cooldown: Duration::from_secs(30),
+            max_cooldown: Duration::from_mins(10),
+            reprobe_interval: Duration::from_secs(15),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CircuitState {
+    /// healthy, or at least not failing enough to be taken out of rotation
+    Closed,
+    /// out of rotation until its cooldown runs out
+    Open,
+    /// cooldown is over, the next request or probe decides whether it closes again
+    HalfOpen,
+}
+
+#[derive(Debug, Clone)]
+pub struct MirrorHealth {
+    /// moving average of failed requests, from 0 (never fails) to 1 (always fails)
+    pub error_rate: f64,
+    /// moving average of how long successful requests took
+    pub latency: Option<Duration>,
+    /// bytes per second the mirror streamed audio at when it was last probed. only streaming
+    /// mirrors have one
+    pub throughput: Option<f64>,
+    pub consecutive_failures: u32,
+    open_until: Option<Instant>,
+    cooldown: Duration,
+}
+
+impl MirrorHealth {
+    fn new(policy: &HealthPolicy) -> Self {
+        Self {
+            error_rate: 0.0,
+            latency: None,
+            throughput: None,
+            consecutive_failures: 0,
+            open_until: None,
+            cooldown: policy.cooldown,
+        }
+    }
+
+    pub fn state(&self) -> CircuitState {
+        match self.open_until {
+            None => CircuitState::Closed,
+            Some(until) if Instant::now() < until => CircuitState::Open,
+            Some(_) => CircuitState::HalfOpen,
+        }
+    }
+
+    /// how much traffic the mirror should get relative to the others. faster mirrors get more,
+    /// and one that fails half its requests gets half of what it otherwise would. for a
+    /// streaming mirror, faster means the time to a segment's last byte, not just its first
+    pub fn weight(&self) -> f64 {
+        let mut seconds = self.latency.unwrap_or(UNKNOWN_LATENCY).as_secs_f64();
+        if let Some(throughput) = self.throughput {
+            seconds += TRANSFER_BYTES / throughput.max(1.0);
+        }
+        (1.0 - self.error_rate).max(0.05) / seconds.max(0.01)
+    }
+}
+
+/// health of every mirror we've sent a request to, keyed by base url
+#[derive(Debug)]
+pub(crate) struct HealthTracker {
+    policy: HealthPolicy,
+    mirrors: Mutex<HashMap<Url, MirrorHealth>>,
+}
+
+impl HealthTracker {
+    pub(crate) fn new(policy: HealthPolicy) -> Self {
+        Self {
+            policy,
+            mirrors: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub(crate) fn policy(&self) -> &HealthPolicy {
+        &self.policy
+    }
+
+    pub(crate) fn record_success(&self, url: &Url, latency: Option<Duration>) {
+        let mut mirrors = self.mirrors.lock().unwrap();
+        let health = mirrors
+            .entry(url.clone())
+            .or_insert_with(|| MirrorHealth::new(&self.policy));
+
+        if health.open_until.is_some() {
+            tracing::info!(%url, "mirror recovered, closing circuit");
+        }
+
+        if let Some(latency) = latency {
+            health.latency = Some(match health.latency {
+                Some(avg) => avg.mul_f64(1.0 - LATENCY_ALPHA) + latency.mul_f64(LATENCY_ALPHA),
+                None => latency,
+            });
+        }
+
+        health.error_rate *= 1.0 - ERROR_RATE_ALPHA;
+        health.consecutive_failures = 0;
+        health.open_until = None;
+        health.cooldown = self.policy.cooldown;
+    }
+
+    pub(crate) fn record_throughput(&self, url: &Url, throughput: f64) {
+        self.mirrors
+            .lock()
+            .unwrap()
+            .entry(url.clone())
+            .or_insert_with(|| MirrorHealth::new(&self.policy))
+            .throughput = Some(throughput);
+    }
+
+    pub(crate) fn record_failure(&self, url: &Url) {
+        let mut mirrors = self.mirrors.lock().unwrap();
+        let health = mirrors
+            .entry(url.clone())
+            .or_insert_with(|| MirrorHealth::new(&self.policy));
+
+        health.error_rate = health.error_rate * (1.0 - ERROR_RATE_ALPHA) + ERROR_RATE_ALPHA;
+        health.consecutive_failures += 1;
+
+        match health.state() {
+            // a failed trial request sends it straight back, for longer this time
+            CircuitState::HalfOpen => {
+                health.cooldown = (health.cooldown * 2).min(self.policy.max_cooldown);
+                health.open_until = Some(Instant::now() + health.cooldown);
+                tracing::warn!(%url, cooldown = ?health.cooldown, "mirror still failing, reopening circuit");
+            }
+            CircuitState::Closed
+                if health.consecutive_failures >= self.policy.failure_threshold =>
+            {
+                health.open_until = Some(Instant::now() + health.cooldown);
+                tracing::warn!(%url, cooldown = ?health.cooldown, "mirror keeps failing, opening circuit");
+            }
+            _ => {}
+        }
+    }
+
+    /// drops mirrors with an open circuit and shuffles the rest, weighted by
+    /// [`MirrorHealth::weight`], so requests spread over every healthy mirror while the faster
+    /// and more reliable ones still get most of them. if every mirror is open they're all
+    /// returned, trying one beats failing outright
+    pub(crate) fn rank(&self, urls: Vec<Url>) -> Vec<Url> {
+        let mirrors = self.mirrors.lock().unwrap();
+        let mut ranked = urls
+            .iter()
+            .filter(|u| {
+                mirrors
+                    .get(*u)
+                    .is_none_or(|h| h.state() != CircuitState::Open)
+            })
+            .cloned()
+            .collect::<Vec<_>>();
+
+        if ranked.is_empty() {
+            ranked = urls;
+        }
+
+        // weighted sampling without replacement: sort by u^(1/w) for a uniform random u
+        let mut keyed = ranked
+            .into_iter()
+            .map(|u| {
+                let weight = self.weight_of(&mirrors, &u);
+                (rand::random::<f64>().powf(1.0 / weight), u)
+            })
+            .collect::<Vec<_>>();
+
+        keyed.sort_by(|a, b| b.0.total_cmp(&a.0));
+        keyed.into_iter().map(|(_, u)| u).collect()
+    }
+
+    /// the mirror that would be picked most often, if there's any to pick from
+    pub(crate) fn best(&self, urls: &[Url]) -> Option<Url> {
+        let mirrors = self.mirrors.lock().unwrap();
+        urls.iter()
+            .filter(|u| {
+                mirrors
+                    .get(*u)
+                    .is_none_or(|h| h.state() != CircuitState::Open)
+            })
+            .max_by(|a, b| {
+                self.weight_of(&mirrors, a)
+                    .total_cmp(&self.weight_of(&mirrors, b))
+            })
+            .or(urls.first())
+            .cloned()
+    }
+
+    fn weight_of(&self, mirrors: &HashMap<Url, MirrorHealth>, url: &Url) -> f64 {
+        mirrors.get(url).map_or_else(
+            || MirrorHealth::new(&self.policy).weight(),
+            MirrorHealth::weight,
+        )
+    }
+
+    /// mirrors whose cooldown has run out and are waiting on a probe
+    pub(crate) fn due_for_probe(&self) -> Vec<Url> {
+        self.mirrors
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(_, h)| h.state() == CircuitState::HalfOpen)
+            .map(|(u, _)| u.clone())
+            .collect()
+    }
+
+    pub(crate) fn snapshot(&self) -> HashMap<Url, MirrorHealth> {
+        self.mirrors.lock().unwrap().clone()
+    }
+}
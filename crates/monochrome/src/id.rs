@@ -1,15 +1,19 @@
+use reqwest::Url;
 use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+use thiserror::Error;
+use uuid::Uuid;
 
 macro_rules! id {
-    ($($id:ident),*$(,)?) => {
+    ($inner:ty => $($id:ident),*$(,)?) => {
         $(
             #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
             #[serde(transparent)]
             #[repr(transparent)]
-            pub struct $id(u64);
+            pub struct $id($inner);
 
-            impl From<u64> for $id {
-                fn from(value: u64) -> Self {
+            impl From<$inner> for $id {
+                fn from(value: $inner) -> Self {
                     Self(value)
                 }
             }
@@ -21,7 +25,7 @@ macro_rules! id {
             }
 
 
-            impl From<$id> for u64 {
+            impl From<$id> for $inner {
                 fn from(value: $id) -> Self {
                     value.0
                 }
@@ -30,4 +34,111 @@ macro_rules! id {
     };
 }
 
-id![TrackId, AlbumId, ArtistId];
+id![u64 => TrackId, AlbumId, ArtistId, VideoId];
+// playlists are keyed by uuid rather than a numeric id
+id![Uuid => PlaylistId];
+
+#[derive(Debug, Error)]
+pub enum ParseTidalRefError {
+    #[error("invalid url: {0}")]
+    Url(#[from] url::ParseError),
+
+    #[error("not a tidal url")]
+    NotTidal,
+
+    #[error("no track, album, artist, playlist or video in url")]
+    MissingRef,
+
+    #[error("invalid id: {0}")]
+    InvalidId(String),
+}
+
+/// a reference to something on tidal, parsed from a share url
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TidalRef {
+    Track(TrackId),
+    Album(AlbumId),
+    Artist(ArtistId),
+    Playlist(PlaylistId),
+    Video(VideoId),
+}
+
+impl TryFrom<&Url> for TidalRef {
+    type Error = ParseTidalRefError;
+
+    fn try_from(url: &Url) -> Result<Self, Self::Error> {
+        // tidal://track/123 puts the kind in the host
+        let mut segments = Vec::new();
+        match url.scheme() {
+            "tidal" => segments.extend(url.host_str()),
+            "http" | "https" => {
+                let host = url.host_str().ok_or(ParseTidalRefError::NotTidal)?;
+                if host != "tidal.com" && !host.ends_with(".tidal.com") {
+                    return Err(ParseTidalRefError::NotTidal);
+                }
+            }
+            _ => return Err(ParseTidalRefError::NotTidal),
+        }
+        segments.extend(url.path_segments().into_iter().flatten());
+
+        // the last kind/id pair wins, so album/1/track/2 is the track
+        let (kind, id) = segments
+            .windows(2)
+            .rev()
+            .find_map(|w| {
+                let kind = w[0].to_ascii_lowercase();
+                let kind = kind.strip_suffix('s').unwrap_or(&kind).to_string();
+                is_kind(Some(&kind)).then(|| (kind, w[1]))
+            })
+            .ok_or(ParseTidalRefError::MissingRef)?;
+
+        let numeric = || {
+            id.parse::<u64>()
+                .map_err(|_| ParseTidalRefError::InvalidId(id.to_string()))
+        };
+
+        Ok(match kind.as_str() {
+            "track" => TidalRef::Track(numeric()?.into()),
+            "album" => TidalRef::Album(numeric()?.into()),
+            "artist" => TidalRef::Artist(numeric()?.into()),
+            "video" => TidalRef::Video(numeric()?.into()),
+            _ => TidalRef::Playlist(
+                id.parse::<Uuid>()
+                    .map_err(|_| ParseTidalRefError::InvalidId(id.to_string()))?
+                    .into(),
+            ),
+        })
+    }
+}
+
+impl FromStr for TidalRef {
+    type Err = ParseTidalRefError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        // people paste links without the scheme too, or just `track/123`
+        let url = match Url::parse(s) {
+            Ok(url) => url,
+            Err(url::ParseError::RelativeUrlWithoutBase) if is_kind(s.split('/').next()) => {
+                Url::parse(&format!("tidal://{s}"))?
+            }
+            Err(url::ParseError::RelativeUrlWithoutBase) => Url::parse(&format!("https://{s}"))?,
+            Err(e) => return Err(e.into()),
+        };
+
+        TidalRef::try_from(&url)
+    }
+}
+
+/// whether a path segment names something a ref can point at, singular or plural
+fn is_kind(segment: Option<&str>) -> bool {
+    let Some(segment) = segment else {
+        return false;
+    };
+
+    let segment = segment.to_ascii_lowercase();
+    matches!(
+        segment.strip_suffix('s').unwrap_or(&segment),
+        "track" | "album" | "artist" | "playlist" | "video"
+    )
+}
@@ -1,214 +1,461 @@
 pub mod album;
+pub mod art;
 pub mod artist;
+pub mod cache;
+pub mod capability;
+mod decrypt;
+pub mod download;
 pub mod endpoint;
 mod error;
+pub mod fixture;
+pub mod health;
 pub mod id;
+pub mod lyrics;
+pub mod manifest;
+#[cfg(feature = "mock")]
+pub mod mock;
+pub mod mpd;
+pub mod page;
+pub mod playlist;
+pub mod quality;
+pub mod ratelimit;
 mod response;
+pub mod search;
+pub mod source;
+pub mod timeout;
 pub mod track;
 
-use std::{fs, sync::Arc, time::Duration};
+use std::{collections::VecDeque, sync::Arc};
 
 use crate::{
     album::{Album, AlbumResult},
-    artist::Artist,
+    art::ArtSize,
+    artist::{Artist, ArtistAlbumFilter, ArtistDetails, Discography},
+    cache::{CacheConfig, CacheKind, MetadataCache},
+    decrypt::TrackKey,
+    download::{
+        AbortOnDrop, DEFAULT_PREFETCH_WINDOW, DownloadStats, Failover, SegmentIndex,
+        SegmentRetryPolicy, TrackStream,
+    },
     endpoint::{Endpoint, FetchKind},
-    error::MonochromeManifestError,
-    id::{AlbumId, TrackId},
+    id::{AlbumId, ArtistId, PlaylistId, TrackId},
+    lyrics::Lyrics,
+    manifest::{Encryption, Manifest},
+    mpd::SegmentPlan,
+    page::Page,
+    playlist::{Playlist, PlaylistCreator, PlaylistResult},
+    quality::AudioQuality,
+    search::{SearchPage, SearchQuery, SearchResults, SearchType},
     track::{Track, TrackManifest},
 };
 use async_stream::try_stream;
 use bytes::Bytes;
-pub use error::MonochromeError;
-use futures::{Stream, StreamExt, stream::FuturesOrdered};
-use reqwest::Url;
-use roxmltree::Document;
+pub use error::{
+    MonochromeError, MonochromeManifestError, RequestKind, is_transient, is_transient_request,
+};
+use futures::Stream;
 use serde::{Deserialize, Deserializer};
 use tokio::sync::Semaphore;
 use uuid::Uuid;
 
-const RESOURCES_URL: &'static str = "https://resources.tidal.com/images";
+const RESOURCES_URL: &str = "https://resources.tidal.com/images";
+const PAGE_LIMIT: u32 = 100;
 
 #[derive(Debug, Clone)]
 pub struct Monochrome {
     endpoint: Endpoint,
+    segment_retry: SegmentRetryPolicy,
+    prefetch_window: usize,
+    segment_fanout: usize,
+    cache: Option<Arc<MetadataCache>>,
 }
 
 impl Monochrome {
     pub fn new(endpoint: Endpoint) -> Self {
-        Self { endpoint }
+        Self {
+            endpoint,
+            segment_retry: SegmentRetryPolicy::default(),
+            prefetch_window: DEFAULT_PREFETCH_WINDOW,
+            segment_fanout: 1,
+            cache: None,
+        }
+    }
+
+    /// caches album, track, search and artist responses
+    pub fn with_cache(mut self, config: CacheConfig) -> Self {
+        self.cache = Some(Arc::new(MetadataCache::new(config)));
+        self
+    }
+
+    async fn cached<T, F, Fut>(
+        &self,
+        kind: CacheKind,
+        key: String,
+        fetch: F,
+    ) -> Result<T, MonochromeError>
+    where
+        T: serde::Serialize + serde::de::DeserializeOwned,
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<T, MonochromeError>>,
+    {
+        match &self.cache {
+            Some(cache) => cache.get_or_fetch(kind, key, fetch).await,
+            None => fetch().await,
+        }
+    }
+
+    /// how many segments of a single track can be downloading or waiting to be consumed at once
+    pub fn with_prefetch_window(mut self, window: usize) -> Self {
+        self.prefetch_window = window.max(1);
+        self
+    }
+
+    /// how many streaming mirrors the segments of a single track are spread across. anything
+    /// above 1 fetches the manifest from that many extra mirrors before the download starts
+    pub fn with_segment_fanout(mut self, mirrors: usize) -> Self {
+        self.segment_fanout = mirrors.max(1);
+        self
     }
 
+    pub fn with_segment_retry(mut self, policy: SegmentRetryPolicy) -> Self {
+        self.segment_retry = policy;
+        self
+    }
+
+    /// the manifest for `quality`. a preview is refused with [`MonochromeManifestError::Preview`]
+    /// rather than handed out as if it were the full track
     pub async fn track_manifest(
         &self,
         id: impl Into<TrackId>,
+        quality: AudioQuality,
     ) -> Result<TrackManifest, MonochromeError> {
-        self.endpoint
+        let mut manifest: TrackManifest = self
+            .endpoint
             .fetch(
                 "track",
-                FetchKind::Streaming,
+                FetchKind::Track(quality),
                 [
                     ("id", id.into().to_string().as_ref()),
-                    ("quality", "HI_RES_LOSSLESS"),
+                    ("quality", quality.as_str()),
                 ],
             )
-            .await
+            .await?;
+
+        if manifest.asset_presentation == "PREVIEW" {
+            return Err(MonochromeError::Manifest(MonochromeManifestError::Preview));
+        }
+
+        manifest.audio_quality.get_or_insert(quality);
+        Ok(manifest)
+    }
+
+    /// like [`Monochrome::track_manifest`], but walks down the quality ladder when the requested
+    /// tier isn't available, either because the mirror refuses it or only serves a preview.
+    /// the tier that was obtained is reported in [`TrackManifest::audio_quality`]
+    pub async fn track_manifest_with_fallback(
+        &self,
+        id: impl Into<TrackId>,
+        quality: AudioQuality,
+    ) -> Result<TrackManifest, MonochromeError> {
+        let id = id.into();
+        let mut last_err = MonochromeError::Manifest(MonochromeManifestError::Preview);
+
+        for tier in quality.ladder() {
+            match self.track_manifest(id, tier).await {
+                Ok(manifest) => return Ok(manifest),
+                Err(e @ MonochromeError::Manifest(MonochromeManifestError::Preview)) => {
+                    tracing::warn!(track = %id, quality = %tier, "only a preview is available, trying a lower quality");
+                    last_err = e;
+                }
+                // mirrors answer tiers they won't serve with a 4xx
+                Err(e @ MonochromeError::Status { .. }) if !e.is_retryable() => {
+                    tracing::warn!(track = %id, quality = %tier, error = %e, "quality unavailable, trying a lower quality");
+                    last_err = e;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+
+        Err(last_err)
     }
 
     pub async fn download_track(
         &self,
         track: &TrackManifest,
         chunk_semaphore: Arc<Semaphore>,
-    ) -> Result<impl Stream<Item = Result<Bytes, reqwest::Error>>, MonochromeError> {
-        let manifest = track.decode_manifest()?;
-        #[derive(Debug, Deserialize)]
-        struct UrlHolder {
-            urls: Vec<String>,
-        }
-
-        let url = if manifest.contains("<MPD") {
-            return Ok(MaybeMpdStream::Mpd(Box::pin(
-                self.download_mpd(manifest, chunk_semaphore).await?,
-            )));
-        } else if let Ok(urls) = serde_json::from_str::<UrlHolder>(&manifest)
-            && let Some(url) = urls.urls.into_iter().next()
-        {
-            url
-        } else {
-            return Err(MonochromeError::ManifestDecode);
+    ) -> Result<
+        TrackStream<impl Stream<Item = Result<Bytes, MonochromeError>> + use<>>,
+        MonochromeError,
+    > {
+        let manifest = track.parse()?;
+        let info = manifest.stream_info();
+        let stats = Arc::new(DownloadStats::default());
+
+        let bts = match manifest {
+            Manifest::Dash(plan) => {
+                let stream = self
+                    .download_mpd(track, plan, chunk_semaphore, stats.clone())
+                    .await?;
+                return Ok(TrackStream::new(
+                    MaybeMpdStream::Mpd(Box::pin(stream)),
+                    stats,
+                    info,
+                ));
+            }
+            Manifest::Bts(bts) => bts,
         };
 
-        let res = self
-            .endpoint
-            .client()
-            .get(url)
-            .timeout(Duration::from_secs(5))
-            .send()
-            .await?;
-        if res.status() != reqwest::StatusCode::OK {
-            return Err(MonochromeError::Non200(res.text().await?));
-        }
+        let key = match &bts.encryption_type {
+            Encryption::None => None,
+            Encryption::OldAes => {
+                let key_id = bts.key_id.as_deref().ok_or_else(|| {
+                    MonochromeError::KeyDecrypt("manifest has no keyId".to_string())
+                })?;
+                Some(TrackKey::from_key_id(key_id)?)
+            }
+            Encryption::Other(other) => {
+                return Err(MonochromeError::UnsupportedEncryption(other.clone()));
+            }
+        };
 
-        let bytes = res.bytes_stream();
+        let Some(url) = bts.urls.into_iter().next().map(String::from) else {
+            return Err(MonochromeError::ManifestDecode);
+        };
 
-        Ok(MaybeMpdStream::Regular(bytes))
+        let timeouts = self.endpoint.timeouts().media;
+        let res = timeouts.send(self.endpoint.client().get(&url)).await?;
+        let res = timeouts.check(res, RequestKind::Media).await?;
+
+        let bytes = download::resumable(
+            self.endpoint.client(),
+            timeouts,
+            url,
+            res,
+            self.segment_retry.clone(),
+            stats.clone(),
+        );
+        let bytes = decrypt::decrypt(bytes, key);
+
+        Ok(TrackStream::new(
+            MaybeMpdStream::Regular(Box::pin(bytes)),
+            stats,
+            info,
+        ))
     }
 
     async fn download_mpd(
         &self,
-        manifest: String,
+        track: &TrackManifest,
+        plan: SegmentPlan,
         chunk_semaphore: Arc<Semaphore>,
-    ) -> Result<impl Stream<Item = Result<Bytes, reqwest::Error>>, MonochromeManifestError> {
-        let doc = Document::parse(&manifest)?;
-
-        let seg = doc
-            .descendants()
-            .find(|n| n.tag_name().name() == "SegmentTemplate")
-            .ok_or_else(|| MonochromeManifestError::MissingSegmentTemplate)?;
-
-        let init_tpl = seg
-            .attribute("initialization")
-            .ok_or_else(|| MonochromeManifestError::MissingInitializationTemplate)?;
-
-        let media_tpl = seg
-            .attribute("media")
-            .ok_or_else(|| MonochromeManifestError::MissingMedia)?;
-
-        let start_number: u64 = seg
-            .attribute("startNumber")
-            .and_then(|s| s.parse().ok())
-            .unwrap_or(1);
-
-        let mut segment_counts: Vec<u64> = Vec::new();
-        if let Some(tl) = seg
-            .children()
-            .find(|c| c.tag_name().name() == "SegmentTimeline")
-        {
-            for s in tl.children().filter(|c| c.tag_name().name() == "S") {
-                let d: u64 = s
-                    .attribute("d")
-                    .and_then(|v| v.parse().ok())
-                    .ok_or_else(|| MonochromeManifestError::SMissingD)?;
-                let r: i64 = s.attribute("r").and_then(|v| v.parse().ok()).unwrap_or(0);
-                for _ in 0..=(r as usize) {
-                    segment_counts.push(d);
-                }
-            }
-        } else {
-            segment_counts.push(0);
+        stats: Arc<DownloadStats>,
+    ) -> Result<impl Stream<Item = Result<Bytes, MonochromeError>> + use<>, MonochromeError> {
+        tracing::debug!(
+            representation = ?plan.representation_id,
+            bandwidth = ?plan.bandwidth,
+            segments = plan.segments.len(),
+            "planned mpd download"
+        );
+
+        let failover = Arc::new(Failover::new(self.endpoint.clone(), track, &plan));
+        let policy = self.segment_retry.clone();
+        let window = self.prefetch_window;
+
+        // segments go round robin over the original plan and the ones from other mirrors
+        let spread = match self.segment_fanout {
+            1 => Vec::new(),
+            n => failover.spread(n - 1).await,
+        };
+
+        if !spread.is_empty() {
+            tracing::debug!(
+                mirrors = spread.len() + 1,
+                "spreading segments across mirrors"
+            );
         }
 
-        let init_url = Url::parse(init_tpl)?;
-        let media_tpl = media_tpl.to_string();
+        let client = self.endpoint.client();
 
         Ok(try_stream! {
-            let init_bytes = self.endpoint.client().get(init_url).timeout(Duration::from_secs(5)).send().await?.bytes().await?;
-            yield init_bytes;
-
-            let mut handles = Vec::new();
+            if let Some(init) = plan.initialization {
+                let init_bytes = download::fetch_with_failover(
+                    client.clone(),
+                    init,
+                    SegmentIndex::Initialization,
+                    &failover,
+                    &policy,
+                    &stats,
+                )
+                .await?;
+                yield init_bytes;
+            }
 
-            for (idx, _dur) in segment_counts.iter().enumerate() {
-                let client = self.endpoint.client();
-                let sem = chunk_semaphore.clone();
-                let number = start_number + idx as u64;
-                let url = media_tpl.replace("$Number$", &number.to_string());
+            // only `window` segments are ever in flight or buffered, and the next one is only
+            // started once the consumer takes one off the front
+            let mut segments = plan.segments.into_iter().enumerate();
+            let mut in_flight = VecDeque::with_capacity(window);
+
+            loop {
+                while in_flight.len() < window
+                    && let Some((idx, segment)) = segments.next()
+                {
+                    let segment = match idx % (spread.len() + 1) {
+                        0 => segment,
+                        n => spread[n - 1].segments[idx].clone(),
+                    };
+
+                    let client = client.clone();
+                    let sem = chunk_semaphore.clone();
+                    let failover = failover.clone();
+                    let policy = policy.clone();
+                    let stats = stats.clone();
+
+                    in_flight.push_back(AbortOnDrop::spawn(async move {
+                        let _permit = sem.acquire_owned().await?;
+                        download::fetch_with_failover(
+                            client,
+                            segment,
+                            SegmentIndex::Media(idx),
+                            &failover,
+                            &policy,
+                            &stats,
+                        )
+                        .await
+                    }));
+                }
 
-                handles.push(tokio::spawn(async move {
-                    let _permit = sem.acquire_owned().await.unwrap();
-                    client.get(url).timeout(Duration::from_secs(5)).send().await?.bytes().await
-                }));
-            }
+                let Some(next) = in_flight.pop_front() else {
+                    break;
+                };
 
-            for handle in handles {
-                let res = handle.await.unwrap()?;
+                let res = next.await??;
                 yield res;
             }
         })
     }
 
-    pub async fn search_tracks(
+    pub async fn search(
         &self,
-        query: impl AsRef<str>,
-    ) -> Result<Vec<Track>, MonochromeError> {
-        let query = query.as_ref();
+        query: impl Into<SearchQuery>,
+    ) -> Result<SearchPage, MonochromeError> {
+        let query = query.into();
+        let key = query.params().map(|(k, v)| format!("{k}={v}")).join("&");
 
+        self.cached(CacheKind::Search, key, || self.fetch_search(query))
+            .await
+    }
+
+    async fn fetch_search(&self, query: SearchQuery) -> Result<SearchPage, MonochromeError> {
         #[derive(Debug, Deserialize)]
-        struct Res {
-            items: Vec<Track>,
+        struct Albums {
+            albums: Page<AlbumResult>,
         }
 
-        let res: Res = self
-            .endpoint
-            .fetch("search", FetchKind::Api, [("s", query)])
-            .await?;
+        #[derive(Debug, Deserialize)]
+        struct Artists {
+            artists: Page<ArtistDetails>,
+        }
+
+        #[derive(Debug, Deserialize)]
+        struct Playlists {
+            playlists: Page<PlaylistResult>,
+        }
 
-        Ok(res.items)
+        let params = query.params();
+
+        // track results come back as a bare page, everything else is nested under its type
+        Ok(match query.search_type() {
+            SearchType::Tracks => SearchPage::Tracks(
+                self.endpoint
+                    .fetch("search", FetchKind::Api, params)
+                    .await?,
+            ),
+            SearchType::Albums => SearchPage::Albums(
+                self.endpoint
+                    .fetch::<Albums, _>("search", FetchKind::Api, params)
+                    .await?
+                    .albums,
+            ),
+            SearchType::Artists => SearchPage::Artists(
+                self.endpoint
+                    .fetch::<Artists, _>("search", FetchKind::Api, params)
+                    .await?
+                    .artists,
+            ),
+            SearchType::Playlists => SearchPage::Playlists(
+                self.endpoint
+                    .fetch::<Playlists, _>("search", FetchKind::Api, params)
+                    .await?
+                    .playlists,
+            ),
+        })
     }
 
-    pub async fn search_albums(
+    /// searches every result type at once. the query's type filter is ignored, but its limit and
+    /// offset apply to each type
+    pub async fn search_all(
         &self,
-        query: impl AsRef<str>,
-    ) -> Result<Vec<AlbumResult>, MonochromeError> {
-        let query = query.as_ref();
-
-        #[derive(Debug, Deserialize)]
-        struct Res {
-            albums: Albums,
+        query: impl Into<SearchQuery>,
+    ) -> Result<SearchResults, MonochromeError> {
+        let query = query.into();
+
+        let (tracks, albums, artists, playlists) = futures::future::try_join4(
+            self.search(query.clone().kind(SearchType::Tracks)),
+            self.search(query.clone().kind(SearchType::Albums)),
+            self.search(query.clone().kind(SearchType::Artists)),
+            self.search(query.kind(SearchType::Playlists)),
+        )
+        .await?;
+
+        match (tracks, albums, artists, playlists) {
+            (
+                SearchPage::Tracks(tracks),
+                SearchPage::Albums(albums),
+                SearchPage::Artists(artists),
+                SearchPage::Playlists(playlists),
+            ) => Ok(SearchResults {
+                tracks,
+                albums,
+                artists,
+                playlists,
+            }),
+            _ => unreachable!("search returns the requested type"),
         }
+    }
 
-        #[derive(Debug, Deserialize)]
-        struct Albums {
-            items: Vec<AlbumResult>,
+    pub async fn search_tracks(
+        &self,
+        query: impl AsRef<str>,
+    ) -> Result<Vec<Track>, MonochromeError> {
+        match self
+            .search(SearchQuery::new(query.as_ref()).kind(SearchType::Tracks))
+            .await?
+        {
+            SearchPage::Tracks(page) => Ok(page.items),
+            _ => unreachable!("search returns the requested type"),
         }
+    }
 
-        let res: Res = self
-            .endpoint
-            .fetch("search", FetchKind::Api, [("al", query)])
-            .await?;
-
-        Ok(res.albums.items)
+    pub async fn search_albums(
+        &self,
+        query: impl AsRef<str>,
+    ) -> Result<Vec<AlbumResult>, MonochromeError> {
+        match self
+            .search(SearchQuery::new(query.as_ref()).kind(SearchType::Albums))
+            .await?
+        {
+            SearchPage::Albums(page) => Ok(page.items),
+            _ => unreachable!("search returns the requested type"),
+        }
     }
 
     pub async fn album(&self, id: impl Into<id::AlbumId>) -> Result<album::Album, MonochromeError> {
+        let id = id.into();
+        self.cached(CacheKind::Album, id.to_string(), || self.fetch_album(id))
+            .await
+    }
+
+    async fn fetch_album(&self, id: AlbumId) -> Result<album::Album, MonochromeError> {
         #[derive(Debug, Deserialize)]
         #[serde(rename_all = "camelCase")]
         struct AlbumTemp {
@@ -233,11 +480,7 @@ impl Monochrome {
 
         let res: AlbumTemp = self
             .endpoint
-            .fetch(
-                "album",
-                FetchKind::Api,
-                [("id", id.into().to_string().as_str())],
-            )
+            .fetch("album", FetchKind::Api, [("id", id.to_string().as_str())])
             .await?;
 
         let tracks = res
@@ -265,57 +508,238 @@ impl Monochrome {
     }
 
     pub async fn track(&self, id: impl Into<id::TrackId>) -> Result<track::Track, MonochromeError> {
+        let id = id.into().to_string();
+        self.cached(CacheKind::Track, id.clone(), || async {
+            self.endpoint
+                .fetch("track", FetchKind::Api, [("id", id.as_str())])
+                .await
+        })
+        .await
+    }
+
+    pub async fn artist(&self, id: impl Into<ArtistId>) -> Result<Discography, MonochromeError> {
+        let id = id.into();
+        self.cached(CacheKind::Artist, id.to_string(), || self.fetch_artist(id))
+            .await
+    }
+
+    async fn fetch_artist(&self, id: ArtistId) -> Result<Discography, MonochromeError> {
+        let artist = self.artist_details(id).await?;
+
+        let (albums, singles, compilations) = futures::future::try_join3(
+            collect_pages(|offset| self.artist_albums(id, ArtistAlbumFilter::Albums, offset)),
+            collect_pages(|offset| {
+                self.artist_albums(id, ArtistAlbumFilter::EpsAndSingles, offset)
+            }),
+            collect_pages(|offset| self.artist_albums(id, ArtistAlbumFilter::Compilations, offset)),
+        )
+        .await?;
+
+        Ok(Discography {
+            artist,
+            albums,
+            singles,
+            compilations,
+        })
+    }
+
+    /// just the artist, without going through their discography
+    pub async fn artist_details(
+        &self,
+        id: impl Into<ArtistId>,
+    ) -> Result<ArtistDetails, MonochromeError> {
+        let id = id.into().to_string();
+        // kept apart from the discography cached under the bare id
+        self.cached(CacheKind::Artist, format!("details:{id}"), || {
+            self.endpoint
+                .fetch("artist", FetchKind::Api, [("id", id.as_str())])
+        })
+        .await
+    }
+
+    pub async fn artist_albums(
+        &self,
+        id: impl Into<ArtistId>,
+        filter: ArtistAlbumFilter,
+        offset: u32,
+    ) -> Result<Page<AlbumResult>, MonochromeError> {
         self.endpoint
             .fetch(
-                "track",
+                "artist/albums",
                 FetchKind::Api,
-                [("id", id.into().to_string().as_str())],
+                [
+                    ("id", id.into().to_string()),
+                    ("filter", filter.as_str().to_string()),
+                    ("limit", PAGE_LIMIT.to_string()),
+                    ("offset", offset.to_string()),
+                ],
             )
             .await
     }
 
+    pub async fn playlist(&self, id: impl Into<PlaylistId>) -> Result<Playlist, MonochromeError> {
+        #[derive(Debug, Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct PlaylistTemp {
+            pub uuid: PlaylistId,
+            pub title: String,
+            pub description: Option<String>,
+            pub creator: PlaylistCreator,
+            #[serde(default, deserialize_with = "null_on_error")]
+            pub square_image: Option<Uuid>,
+            #[serde(default, deserialize_with = "null_on_error")]
+            pub image: Option<Uuid>,
+        }
+
+        #[derive(Debug, Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct Item {
+            #[serde(rename = "type")]
+            pub kind: String,
+            pub item: serde_json::Value,
+        }
+
+        let id = id.into();
+
+        let res: PlaylistTemp = self
+            .endpoint
+            .fetch(
+                "playlist",
+                FetchKind::Api,
+                [("id", id.to_string().as_str())],
+            )
+            .await?;
+
+        let items: Vec<Item> = collect_pages(|offset| {
+            self.endpoint.fetch(
+                "playlist/items",
+                FetchKind::Api,
+                [
+                    ("id", id.to_string()),
+                    ("limit", PAGE_LIMIT.to_string()),
+                    ("offset", offset.to_string()),
+                ],
+            )
+        })
+        .await?;
+
+        // playlists can contain videos, which don't deserialize as tracks
+        let tracks = items
+            .into_iter()
+            .filter(|i| i.kind == "track")
+            .map(|i| serde_json::from_value(i.item))
+            .collect::<Result<Vec<Track>, _>>()?;
+
+        Ok(Playlist {
+            id: res.uuid,
+            title: res.title,
+            description: res.description,
+            creator: res.creator,
+            cover: res.square_image.or(res.image),
+            tracks,
+        })
+    }
+
+    pub async fn lyrics(&self, id: impl Into<TrackId>) -> Result<Lyrics, MonochromeError> {
+        #[derive(Debug, Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct LyricsTemp {
+            pub track_id: TrackId,
+            pub lyrics_provider: Option<String>,
+            pub lyrics: Option<String>,
+            pub subtitles: Option<String>,
+            #[serde(default)]
+            pub is_right_to_left: bool,
+        }
+
+        let res: LyricsTemp = self
+            .endpoint
+            .fetch(
+                "lyrics",
+                FetchKind::Api,
+                [("id", id.into().to_string().as_str())],
+            )
+            .await?;
+
+        Ok(Lyrics {
+            track_id: res.track_id,
+            provider: res.lyrics_provider,
+            plain: res.lyrics.filter(|l| !l.trim().is_empty()),
+            synced: res
+                .subtitles
+                .as_deref()
+                .map(lyrics::parse_lrc)
+                .unwrap_or_default(),
+            right_to_left: res.is_right_to_left,
+        })
+    }
+
     pub async fn album_art(
         &self,
         album: &Album,
-    ) -> Result<impl Stream<Item = Result<Bytes, reqwest::Error>>, MonochromeError> {
-        self.art(album.cover).await
+        size: ArtSize,
+    ) -> Result<impl Stream<Item = Result<Bytes, MonochromeError>> + Unpin + use<>, MonochromeError>
+    {
+        self.art(album.cover, size).await
+    }
+
+    /// the artist's picture, or `None` if they don't have one
+    pub async fn artist_picture(
+        &self,
+        id: ArtistId,
+        size: ArtSize,
+    ) -> Result<
+        Option<impl Stream<Item = Result<Bytes, MonochromeError>> + Unpin + use<>>,
+        MonochromeError,
+    > {
+        let artist = self.artist_details(id).await?;
+        match artist.picture {
+            Some(picture) => Ok(Some(self.art(picture, size).await?)),
+            None => Ok(None),
+        }
     }
 
+    /// an image from the resources server, in `size` or the closest size that exists
     pub async fn art(
         &self,
         uuid: Uuid,
-    ) -> Result<impl Stream<Item = Result<Bytes, reqwest::Error>>, MonochromeError> {
+        size: ArtSize,
+    ) -> Result<impl Stream<Item = Result<Bytes, MonochromeError>> + Unpin + use<>, MonochromeError>
+    {
         let id = uuid.to_string().replace("-", "/");
-        let url = format!("{RESOURCES_URL}/{id}/1280x1280.jpg");
-        let res = self
-            .endpoint
-            .client()
-            .get(url)
-            .timeout(Duration::from_secs(5))
-            .send()
-            .await?;
-        if res.status() != reqwest::StatusCode::OK {
-            return Err(MonochromeError::Non200(res.text().await?));
+        let timeouts = self.endpoint.timeouts().media;
+
+        for size in size.fallbacks() {
+            let url = format!("{RESOURCES_URL}/{id}/{}", size.file_name());
+            let res = timeouts.send(self.endpoint.client().get(url)).await?;
+
+            if res.status() == reqwest::StatusCode::NOT_FOUND {
+                tracing::debug!(%uuid, %size, "artwork size unavailable, trying another");
+                continue;
+            }
+
+            let res = timeouts.check(res, RequestKind::Artwork).await?;
+            return Ok(Box::pin(timeouts.body(res)));
         }
 
-        Ok(res.bytes_stream())
+        Err(MonochromeError::ArtworkUnavailable(uuid))
     }
 }
 
 pub enum MaybeMpdStream<
-    M: Stream<Item = Result<Bytes, reqwest::Error>> + Unpin,
-    I: Stream<Item = Result<Bytes, reqwest::Error>> + Unpin,
+    M: Stream<Item = Result<Bytes, MonochromeError>> + Unpin,
+    I: Stream<Item = Result<Bytes, MonochromeError>> + Unpin,
 > {
     Mpd(M),
     Regular(I),
 }
 
 impl<
-    M: Stream<Item = Result<Bytes, reqwest::Error>> + Unpin,
-    I: Stream<Item = Result<Bytes, reqwest::Error>> + Unpin,
+    M: Stream<Item = Result<Bytes, MonochromeError>> + Unpin,
+    I: Stream<Item = Result<Bytes, MonochromeError>> + Unpin,
 > Stream for MaybeMpdStream<M, I>
 {
-    type Item = Result<Bytes, reqwest::Error>;
+    type Item = Result<Bytes, MonochromeError>;
 
     fn poll_next(
         mut self: std::pin::Pin<&mut Self>,
@@ -328,6 +752,34 @@ impl<
     }
 }
 
+async fn collect_pages<T, F, Fut>(mut fetch: F) -> Result<Vec<T>, MonochromeError>
+where
+    F: FnMut(u32) -> Fut,
+    Fut: Future<Output = Result<Page<T>, MonochromeError>>,
+{
+    let mut items = Vec::new();
+    let mut offset = 0;
+
+    loop {
+        let page = fetch(offset).await?;
+        // a mirror ignoring the offset answers with items that were already collected
+        if page.offset.is_some_and(|o| o != offset) {
+            tracing::warn!(offset, answered = ?page.offset, "mirror ignored the page offset");
+            break;
+        }
+
+        let next = page.next_offset(offset);
+        items.extend(page.items);
+
+        match next {
+            Some(next) => offset = next,
+            None => break,
+        }
+    }
+
+    Ok(items)
+}
+
 fn null_on_error<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
 where
     D: Deserializer<'de>,
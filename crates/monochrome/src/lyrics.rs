@@ -0,0 +1,103 @@
+use crate::id::TrackId;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::{fmt::Write, sync::LazyLock, time::Duration};
+
+static LRC_TIMESTAMP: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^\[(\d+):(\d{1,2})(?:[.:](\d{1,3}))?\]").unwrap());
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Lyrics {
+    pub track_id: TrackId,
+    pub provider: Option<String>,
+    /// unsynced lyrics, one line per line
+    pub plain: Option<String>,
+    /// time-synced lines, in order. empty if the provider only has plain lyrics
+    pub synced: Vec<SyncedLine>,
+    pub right_to_left: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct SyncedLine {
+    pub time: Duration,
+    pub text: String,
+}
+
+impl Lyrics {
+    /// renders the synced lines as an lrc file, or `None` if there are no synced lines
+    pub fn to_lrc(&self) -> Option<String> {
+        if self.synced.is_empty() {
+            return None;
+        }
+
+        let mut lrc = String::new();
+        for line in &self.synced {
+            let centis = line.time.as_millis() / 10;
+            writeln!(
+                lrc,
+                "[{:02}:{:02}.{:02}]{}",
+                centis / 6000,
+                (centis / 100) % 60,
+                centis % 100,
+                line.text
+            )
+            .ok();
+        }
+
+        Some(lrc)
+    }
+}
+
+/// parses `[mm:ss.xx] text` lines, skipping anything that isn't a timestamped line (tags like
+/// `[ar:...]`, blank lines). a line with several timestamps, like `[00:01.00][00:05.00] text`, is
+/// repeated at each of them, and the result is sorted by time
+pub fn parse_lrc(lrc: &str) -> Vec<SyncedLine> {
+    let mut lines = Vec::new();
+
+    for line in lrc.lines() {
+        let mut rest = line.trim();
+        let mut times = Vec::new();
+
+        while let Some(caps) = LRC_TIMESTAMP.captures(rest) {
+            // a timestamp too large to represent makes the whole line suspect
+            let Some(time) = timestamp(&caps) else {
+                times.clear();
+                break;
+            };
+            times.push(time);
+            rest = &rest[caps[0].len()..];
+        }
+
+        let text = rest.strip_prefix(char::is_whitespace).unwrap_or(rest);
+        lines.extend(times.into_iter().map(|time| SyncedLine {
+            time,
+            text: text.trim_end().to_string(),
+        }));
+    }
+
+    // stable, so lines sharing a timestamp keep their order
+    lines.sort_by_key(|line| line.time);
+    lines
+}
+
+fn timestamp(caps: &regex::Captures) -> Option<Duration> {
+    let minutes: u64 = caps[1].parse().ok()?;
+    let seconds: u64 = caps[2].parse().ok()?;
+    let millis = match caps.get(3) {
+        // .x is tenths, .xx hundredths, .xxx millis
+        Some(frac) => {
+            let digits = frac.as_str();
+            let value: u64 = digits.parse().ok()?;
+            value * 10u64.pow(3 - digits.len() as u32)
+        }
+        None => 0,
+    };
+
+    let total = minutes
+        .checked_mul(60)?
+        .checked_add(seconds)?
+        .checked_mul(1000)?
+        .checked_add(millis)?;
+    Some(Duration::from_millis(total))
+}
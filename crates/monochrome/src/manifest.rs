@@ -0,0 +1,210 @@
+use std::fmt::Display;
+
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+
+use crate::mpd::SegmentPlan;
+
+/// what the manifest in a [`TrackManifest`](crate::track::TrackManifest) is, going by its mime
+/// type
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(from = "String", into = "String")]
+pub enum ManifestKind {
+    /// a dash mpd, `application/dash+xml`
+    Dash,
+    /// json with a list of urls to the whole file, `application/vnd.tidal.bts`
+    Bts,
+    /// anything else, kept around so the error can say what it was
+    Other(String),
+}
+
+impl ManifestKind {
+    pub fn mime_type(&self) -> &str {
+        match self {
+            ManifestKind::Dash => "application/dash+xml",
+            ManifestKind::Bts => "application/vnd.tidal.bts",
+            ManifestKind::Other(mime) => mime,
+        }
+    }
+}
+
+impl From<String> for ManifestKind {
+    fn from(value: String) -> Self {
+        match value.as_str() {
+            "application/dash+xml" => ManifestKind::Dash,
+            "application/vnd.tidal.bts" => ManifestKind::Bts,
+            _ => ManifestKind::Other(value),
+        }
+    }
+}
+
+impl From<ManifestKind> for String {
+    fn from(value: ManifestKind) -> Self {
+        value.mime_type().to_string()
+    }
+}
+
+/// how the audio is encrypted, if at all
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[serde(from = "String", into = "String")]
+pub enum Encryption {
+    #[default]
+    None,
+    /// aes-128-ctr, with the key wrapped in the manifest's `keyId`
+    OldAes,
+    Other(String),
+}
+
+impl Encryption {
+    pub fn as_str(&self) -> &str {
+        match self {
+            Encryption::None => "NONE",
+            Encryption::OldAes => "OLD_AES",
+            Encryption::Other(other) => other,
+        }
+    }
+}
+
+impl From<String> for Encryption {
+    fn from(value: String) -> Self {
+        match value.as_str() {
+            "NONE" => Encryption::None,
+            "OLD_AES" => Encryption::OldAes,
+            _ => Encryption::Other(value),
+        }
+    }
+}
+
+impl From<Encryption> for String {
+    fn from(value: Encryption) -> Self {
+        value.as_str().to_string()
+    }
+}
+
+/// the json behind [`ManifestKind::Bts`]
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BtsManifest {
+    pub mime_type: String,
+    #[serde(default)]
+    pub codecs: Option<String>,
+    #[serde(default)]
+    pub encryption_type: Encryption,
+    #[serde(default)]
+    pub key_id: Option<String>,
+    pub urls: Vec<Url>,
+    /// not in the json itself but in the track manifest around it, see [`TrackManifest::parse`]
+    ///
+    /// [`TrackManifest::parse`]: crate::track::TrackManifest::parse
+    #[serde(skip)]
+    pub bit_depth: Option<u32>,
+    #[serde(skip)]
+    pub sample_rate: Option<u32>,
+}
+
+/// a decoded manifest, ready to download from
+#[derive(Debug, Clone)]
+pub enum Manifest {
+    Dash(SegmentPlan),
+    Bts(BtsManifest),
+}
+
+impl Manifest {
+    pub fn stream_info(&self) -> StreamInfo {
+        match self {
+            // the representation id is only a guess at the format, for when nothing else says
+            Manifest::Dash(plan) => {
+                let (rate, depth) = plan
+                    .representation_id
+                    .as_deref()
+                    .map(rate_and_depth)
+                    .unwrap_or_default();
+
+                StreamInfo {
+                    codec: plan.codecs.clone(),
+                    container: plan.mime_type.as_deref().map(container),
+                    bit_depth: plan.bit_depth.or(depth),
+                    sample_rate: plan.sample_rate.or(rate),
+                    encryption: Encryption::None,
+                }
+            }
+            Manifest::Bts(bts) => StreamInfo {
+                codec: bts.codecs.clone(),
+                container: Some(container(&bts.mime_type)),
+                bit_depth: bts.bit_depth,
+                sample_rate: bts.sample_rate,
+                encryption: bts.encryption_type.clone(),
+            },
+        }
+    }
+}
+
+/// what's actually in the stream, as far as the manifest says. anything the manifest leaves out
+/// is `None`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StreamInfo {
+    /// e.g. `flac` or `mp4a.40.2`
+    pub codec: Option<String>,
+    /// e.g. `mp4` or `flac`
+    pub container: Option<String>,
+    pub bit_depth: Option<u32>,
+    pub sample_rate: Option<u32>,
+    pub encryption: Encryption,
+}
+
+impl StreamInfo {
+    /// the codec the way people know it, so `AAC` rather than `mp4a.40.2`
+    pub fn codec_name(&self) -> Option<String> {
+        let codec = self.codec.as_deref()?;
+        Some(match codec {
+            c if c.starts_with("mp4a") => "AAC".to_string(),
+            "ec-3" => "E-AC-3".to_string(),
+            "ac-4" => "AC-4".to_string(),
+            c => c.to_uppercase(),
+        })
+    }
+}
+
+/// e.g. `FLAC 24-bit/96 kHz`
+impl Display for StreamInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut parts = Vec::new();
+
+        if let Some(codec) = self.codec_name() {
+            parts.push(codec);
+        }
+
+        let depth = self.bit_depth.map(|d| format!("{d}-bit"));
+        let rate = self
+            .sample_rate
+            .map(|r| format!("{} kHz", r as f64 / 1000.0));
+        match (depth, rate) {
+            (Some(depth), Some(rate)) => parts.push(format!("{depth}/{rate}")),
+            (depth, rate) => parts.extend(depth.or(rate)),
+        }
+
+        if self.encryption != Encryption::None {
+            parts.push("(encrypted)".to_string());
+        }
+
+        if parts.is_empty() {
+            write!(f, "unknown format")
+        } else {
+            write!(f, "{}", parts.join(" "))
+        }
+    }
+}
+
+/// `audio/mp4` is `mp4`
+fn container(mime_type: &str) -> String {
+    let subtype = mime_type.rsplit('/').next().unwrap_or(mime_type);
+    subtype.trim_start_matches("x-").to_string()
+}
+
+/// representations are named like `FLAC,44100,16`, which is the only place the bit depth shows up
+fn rate_and_depth(id: &str) -> (Option<u32>, Option<u32>) {
+    let mut parts = id.split(',').skip(1);
+    let rate = parts.next().and_then(|r| r.trim().parse().ok());
+    let depth = parts.next().and_then(|d| d.trim().parse().ok());
+    (rate, depth)
+}
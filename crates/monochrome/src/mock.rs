@@ -0,0 +1,555 @@
+//! a local server that impersonates the uptime worker, an api mirror and a streaming mirror, so
+//! [`Endpoint`] and everything built on it can be tested without the network
+
+use std::{
+    collections::VecDeque,
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use aes::cipher::StreamCipher;
+use base64::{Engine, prelude::BASE64_STANDARD};
+use bytes::{Bytes, BytesMut};
+use reqwest::Url;
+use serde_json::json;
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+    task::JoinSet,
+};
+
+use crate::{
+    capability::PROBE_TRACK,
+    decrypt::TrackKey,
+    download::AbortOnDrop,
+    endpoint::Endpoint,
+    quality::AudioQuality,
+    source::{MirrorList, UptimeSource},
+};
+
+/// something to go wrong with the next request to a route
+#[derive(Debug, Clone)]
+pub enum Fault {
+    /// responds with this status and an empty body
+    Status(u16),
+    /// a 429 with a `Retry-After` header
+    RateLimited { retry_after_secs: u64 },
+    /// accepts the request and never responds
+    Hang,
+    /// advertises the full body but closes the connection after this many bytes of it
+    Truncate(usize),
+    /// answers a range request with a 206 starting at this byte, whatever was asked for
+    RangeFrom(usize),
+}
+
+#[derive(Debug, Clone)]
+struct Canned {
+    status: u16,
+    content_type: &'static str,
+    body: Bytes,
+}
+
+#[derive(Debug)]
+struct Route {
+    path: String,
+    // `*` matches any value, as long as the parameter is there
+    query: Vec<(String, String)>,
+    response: Canned,
+    faults: VecDeque<Fault>,
+    hits: usize,
+}
+
+impl Route {
+    fn matches(&self, path: &str, query: &[(String, String)]) -> bool {
+        self.path == path
+            && self.query.iter().all(|(k, v)| {
+                query
+                    .iter()
+                    .any(|(qk, qv)| qk == k && (v == "*" || qv == v))
+            })
+    }
+}
+
+#[derive(Debug, Default)]
+struct State {
+    routes: Vec<Route>,
+    uptime: MirrorList,
+    // requests that haven't been answered yet, hanging ones included
+    in_flight: usize,
+}
+
+/// counts a request as in flight until the connection handling it is done
+struct InFlight(Arc<Mutex<State>>);
+
+impl InFlight {
+    fn start(state: &Arc<Mutex<State>>) -> Self {
+        state.lock().unwrap().in_flight += 1;
+        Self(state.clone())
+    }
+}
+
+impl Drop for InFlight {
+    fn drop(&mut self) {
+        self.0.lock().unwrap().in_flight -= 1;
+    }
+}
+
+pub struct MockServer {
+    addr: SocketAddr,
+    state: Arc<Mutex<State>>,
+    _task: AbortOnDrop<()>,
+}
+
+impl MockServer {
+    /// starts listening on a random local port. the server lists itself as the only api and
+    /// streaming mirror, and answers the probes [`Endpoint::scan`] sends
+    pub async fn start() -> std::io::Result<Self> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let addr = listener.local_addr()?;
+        let state = Arc::new(Mutex::new(State::default()));
+
+        let task = AbortOnDrop::spawn({
+            let state = state.clone();
+            async move {
+                // dropping the set aborts every connection, including the hanging ones
+                let mut connections = JoinSet::new();
+                while let Ok((stream, _)) = listener.accept().await {
+                    connections.spawn(handle(stream, state.clone()));
+                }
+            }
+        });
+
+        let server = Self {
+            addr,
+            state,
+            _task: task,
+        };
+
+        let url = server.url();
+        server.set_uptime(MirrorList {
+            api: vec![url.clone()],
+            streaming: vec![url],
+        });
+        server.data(
+            "/album?id=109485854",
+            sample_album(109485854, &[PROBE_TRACK]),
+        );
+        server.bts_track(PROBE_TRACK, vec![0; 64 * 1024]);
+        server.probe_quality(AudioQuality::HiResLossless);
+
+        Ok(server)
+    }
+
+    pub fn url(&self) -> Url {
+        format!("http://{}/", self.addr).parse().unwrap()
+    }
+
+    pub fn uptime_url(&self) -> Url {
+        self.url().join("uptime").unwrap()
+    }
+
+    /// an endpoint that discovers its mirrors through this server's uptime route, and uses this
+    /// server until the first scan
+    pub fn endpoint(&self) -> Endpoint {
+        Endpoint::new()
+            .with_sources(vec![Box::new(UptimeSource::new(self.uptime_url()))])
+            .with_mirrors(MirrorList {
+                api: vec![self.url()],
+                streaming: vec![self.url()],
+            })
+    }
+
+    /// what the uptime route lists, which can include other mock servers
+    pub fn set_uptime(&self, mirrors: MirrorList) {
+        let body = json!({
+            "api": mirrors.api.iter().map(|u| json!({ "url": u })).collect::<Vec<_>>(),
+            "streaming": mirrors.streaming.iter().map(|u| json!({ "url": u })).collect::<Vec<_>>(),
+        });
+
+        self.state.lock().unwrap().uptime = mirrors;
+        self.respond("/uptime", 200, "application/json", body.to_string());
+    }
+
+    /// serves `body` for requests to `target`, a path with an optional query. every parameter
+    /// in the query has to be in the request, the most specific matching route wins
+    pub fn respond(
+        &self,
+        target: &str,
+        status: u16,
+        content_type: &'static str,
+        body: impl Into<Bytes>,
+    ) {
+        let url = Url::parse("http://mock").unwrap().join(target).unwrap();
+        let query = url.query_pairs().into_owned().collect::<Vec<_>>();
+        let response = Canned {
+            status,
+            content_type,
+            body: body.into(),
+        };
+
+        let mut state = self.state.lock().unwrap();
+        if let Some(route) = state
+            .routes
+            .iter_mut()
+            .find(|r| r.path == url.path() && r.query == query)
+        {
+            route.response = response;
+            return;
+        }
+
+        state.routes.push(Route {
+            path: url.path().to_string(),
+            query,
+            response,
+            faults: VecDeque::new(),
+            hits: 0,
+        });
+    }
+
+    /// serves `data` the way mirrors do, wrapped in `{ "data": ... }`
+    pub fn data(&self, target: &str, data: serde_json::Value) {
+        let body = json!({ "version": "mock", "data": data });
+        self.respond(target, 200, "application/json", body.to_string());
+    }
+
+    /// serves a track manifest for any quality. track metadata lives on the same path without a
+    /// quality, so that can still be served with [`MockServer::data`]
+    pub fn manifest(&self, track_id: u64, mime_type: &str, manifest: &[u8]) {
+        self.data(
+            &format!("/track?id={track_id}&quality=*"),
+            manifest_data(track_id, mime_type, manifest),
+        );
+    }
+
+    /// a dash track split into an init segment and `segments`, all served by this server
+    pub fn dash_track(&self, track_id: u64, init: impl Into<Bytes>, segments: Vec<Bytes>) {
+        let base = self.url();
+        let mpd = format!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<MPD xmlns="urn:mpeg:dash:schema:mpd:2011" type="static" mediaPresentationDuration="PT{count}S">
+  <Period id="0">
+    <AdaptationSet id="0" contentType="audio" mimeType="audio/mp4">
+      <Representation id="FLAC,44100,16" codecs="flac" bandwidth="1000" audioSamplingRate="44100">
+        <SegmentTemplate timescale="1000" initialization="{base}segments/{track_id}/init.mp4" media="{base}segments/{track_id}/$Number$.mp4" startNumber="1">
+          <SegmentTimeline>
+            <S d="1000" r="{repeat}"/>
+          </SegmentTimeline>
+        </SegmentTemplate>
+      </Representation>
+    </AdaptationSet>
+  </Period>
+</MPD>"#,
+            count = segments.len(),
+            repeat = segments.len().saturating_sub(1),
+        );
+
+        self.manifest(track_id, "application/dash+xml", mpd.as_bytes());
+        self.respond(
+            &format!("/segments/{track_id}/init.mp4"),
+            200,
+            "audio/mp4",
+            init,
+        );
+        for (i, segment) in segments.into_iter().enumerate() {
+            self.respond(
+                &format!("/segments/{track_id}/{}.mp4", i + 1),
+                200,
+                "audio/mp4",
+                segment,
+            );
+        }
+    }
+
+    /// a single file track, served with range support from `/files/{track_id}.flac`
+    pub fn bts_track(&self, track_id: u64, file: impl Into<Bytes>) {
+        let url = self.url().join(&format!("files/{track_id}.flac")).unwrap();
+        let manifest = json!({
+            "mimeType": "audio/flac",
+            "codecs": "flac",
+            "encryptionType": "NONE",
+            "urls": [url],
+        });
+
+        self.manifest(
+            track_id,
+            "application/vnd.tidal.bts",
+            manifest.to_string().as_bytes(),
+        );
+        self.respond(&format!("/files/{track_id}.flac"), 200, "audio/flac", file);
+    }
+
+    /// how the track scans probe with answers each tier: in full up to `max`, and only as a
+    /// preview above it, the way mirrors without a hi-res account do
+    pub fn probe_quality(&self, max: AudioQuality) {
+        let url = self
+            .url()
+            .join(&format!("files/{PROBE_TRACK}.flac"))
+            .unwrap();
+        let manifest = json!({
+            "mimeType": "audio/flac",
+            "codecs": "flac",
+            "encryptionType": "NONE",
+            "urls": [url],
+        });
+
+        let mut full = false;
+        for tier in AudioQuality::HiResLossless.ladder() {
+            full |= tier == max;
+
+            let mut data = manifest_data(
+                PROBE_TRACK,
+                "application/vnd.tidal.bts",
+                manifest.to_string().as_bytes(),
+            );
+            data["assetPresentation"] = json!(if full { "FULL" } else { "PREVIEW" });
+            data["audioQuality"] = json!(tier);
+
+            self.data(&format!("/track?id={PROBE_TRACK}&quality={tier}"), data);
+        }
+    }
+
+    /// like [`MockServer::bts_track`], but `OLD_AES` encrypted the way some mirrors serve it.
+    /// `file` is the plaintext a download should come out as
+    pub fn encrypted_bts_track(&self, track_id: u64, file: impl Into<Bytes>) {
+        let key = TrackKey::new([7; 16], [9; 8]);
+        let mut body = BytesMut::from(file.into());
+        key.cipher().apply_keystream(&mut body);
+
+        let url = self.url().join(&format!("files/{track_id}.flac")).unwrap();
+        let manifest = json!({
+            "mimeType": "audio/flac",
+            "codecs": "flac",
+            "encryptionType": "OLD_AES",
+            "keyId": key.to_key_id([3; 16]),
+            "urls": [url],
+        });
+
+        self.manifest(
+            track_id,
+            "application/vnd.tidal.bts",
+            manifest.to_string().as_bytes(),
+        );
+        self.respond(
+            &format!("/files/{track_id}.flac"),
+            200,
+            "audio/flac",
+            body.freeze(),
+        );
+    }
+
+    /// makes the next request to `target` fail. faults queue up, one per request
+    pub fn inject(&self, target: &str, fault: Fault) {
+        let url = Url::parse("http://mock").unwrap().join(target).unwrap();
+        let query = url.query_pairs().into_owned().collect::<Vec<_>>();
+
+        let mut state = self.state.lock().unwrap();
+        let route = state
+            .routes
+            .iter_mut()
+            .find(|r| r.path == url.path() && r.query == query)
+            .unwrap_or_else(|| panic!("no route for {target}"));
+
+        route.faults.push_back(fault);
+    }
+
+    /// how many requests are being answered right now. a hanging request stops counting once
+    /// the client gives up on it and closes the connection
+    pub fn in_flight(&self) -> usize {
+        self.state.lock().unwrap().in_flight
+    }
+
+    /// how many requests every route with this path has received, faults included
+    pub fn hits(&self, path: &str) -> usize {
+        self.state
+            .lock()
+            .unwrap()
+            .routes
+            .iter()
+            .filter(|r| r.path == path)
+            .map(|r| r.hits)
+            .sum()
+    }
+}
+
+/// an album in the shape the api mirror returns it, with one track per id
+pub fn sample_album(id: u64, track_ids: &[u64]) -> serde_json::Value {
+    let artist = json!({ "id": 1, "name": "mock artist", "type": "MAIN" });
+    let cover = "00000000-0000-0000-0000-000000000000";
+
+    let items = track_ids
+        .iter()
+        .enumerate()
+        .map(|(i, track_id)| {
+            json!({
+                "type": "track",
+                "item": {
+                    "id": track_id,
+                    "title": format!("mock track {}", i + 1),
+                    "artist": artist,
+                    "artists": [artist],
+                    "album": { "id": id, "title": "mock album", "cover": cover },
+                    "duration": 60,
+                    "trackNumber": i + 1,
+                    "volumeNumber": 1,
+                    "streamStartDate": null,
+                },
+            })
+        })
+        .collect::<Vec<_>>();
+
+    json!({
+        "id": id,
+        "title": "mock album",
+        "releaseDate": "2020-01-01",
+        "artist": artist,
+        "artists": [artist],
+        "cover": cover,
+        "type": "ALBUM",
+        "items": items,
+    })
+}
+
+fn manifest_data(track_id: u64, mime_type: &str, manifest: &[u8]) -> serde_json::Value {
+    json!({
+        "trackId": track_id,
+        "assetPresentation": "FULL",
+        "manifestMimeType": mime_type,
+        "manifest": BASE64_STANDARD.encode(manifest),
+        "audioQuality": "LOSSLESS",
+    })
+}
+
+async fn handle(mut stream: TcpStream, state: Arc<Mutex<State>>) {
+    let mut buf = Vec::new();
+    let mut chunk = [0; 4096];
+
+    // requests are only ever GETs, so everything up to the blank line is all there is
+    while !buf.windows(4).any(|w| w == b"\r\n\r\n") {
+        match stream.read(&mut chunk).await {
+            Ok(0) | Err(_) => return,
+            Ok(n) => buf.extend_from_slice(&chunk[..n]),
+        }
+    }
+
+    let _in_flight = InFlight::start(&state);
+
+    let request = String::from_utf8_lossy(&buf);
+    let mut lines = request.lines();
+    let Some(target) = lines.next().and_then(|l| l.split_whitespace().nth(1)) else {
+        return;
+    };
+
+    let range_start = lines
+        .filter_map(|l| l.split_once(':'))
+        .find(|(k, _)| k.eq_ignore_ascii_case("range"))
+        .and_then(|(_, v)| v.trim().strip_prefix("bytes="))
+        .and_then(|v| v.split_once('-'))
+        .and_then(|(start, _)| start.parse::<usize>().ok());
+
+    let url = Url::parse("http://mock").unwrap().join(target).unwrap();
+    let query = url.query_pairs().into_owned().collect::<Vec<_>>();
+
+    let (response, fault) = {
+        let mut state = state.lock().unwrap();
+        let route = state
+            .routes
+            .iter_mut()
+            .filter(|r| r.matches(url.path(), &query))
+            .max_by_key(|r| r.query.len());
+
+        match route {
+            Some(route) => {
+                route.hits += 1;
+                (route.response.clone(), route.faults.pop_front())
+            }
+            None => (
+                Canned {
+                    status: 404,
+                    content_type: "text/plain",
+                    body: Bytes::from_static(b"not found"),
+                },
+                None,
+            ),
+        }
+    };
+
+    let mut headers = Vec::new();
+    let (status, body) = match fault {
+        Some(Fault::Hang) => {
+            // never answers, but notices when the client closes the connection
+            tokio::time::timeout(Duration::from_secs(3600), stream.read(&mut chunk))
+                .await
+                .ok();
+            return;
+        }
+        Some(Fault::Status(status)) => (status, Bytes::new()),
+        Some(Fault::RateLimited { retry_after_secs }) => {
+            headers.push(format!("Retry-After: {retry_after_secs}"));
+            (429, Bytes::new())
+        }
+        Some(Fault::Truncate(n)) => {
+            let head = format!(
+                "HTTP/1.1 {} OK\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                response.status,
+                response.content_type,
+                response.body.len()
+            );
+            stream.write_all(head.as_bytes()).await.ok();
+            stream
+                .write_all(&response.body[..n.min(response.body.len())])
+                .await
+                .ok();
+            return;
+        }
+        Some(Fault::RangeFrom(start)) => {
+            let start = start.min(response.body.len());
+            headers.push(format!(
+                "Content-Range: bytes {start}-{}/{}",
+                response.body.len() - 1,
+                response.body.len()
+            ));
+            (206, response.body.slice(start..))
+        }
+        None => match range_start {
+            Some(start) if response.status == 200 && start < response.body.len() => {
+                headers.push(format!(
+                    "Content-Range: bytes {start}-{}/{}",
+                    response.body.len() - 1,
+                    response.body.len()
+                ));
+                (206, response.body.slice(start..))
+            }
+            _ => (response.status, response.body),
+        },
+    };
+
+    let mut head = format!(
+        "HTTP/1.1 {status} {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n",
+        reason(status),
+        response.content_type,
+        body.len()
+    );
+    for header in headers {
+        head.push_str(&header);
+        head.push_str("\r\n");
+    }
+    head.push_str("\r\n");
+
+    stream.write_all(head.as_bytes()).await.ok();
+    stream.write_all(&body).await.ok();
+    stream.shutdown().await.ok();
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        206 => "Partial Content",
+        404 => "Not Found",
+        429 => "Too Many Requests",
+        500 => "Internal Server Error",
+        502 => "Bad Gateway",
+        503 => "Service Unavailable",
+        _ => "Unknown",
+    }
+}
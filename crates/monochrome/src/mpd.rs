@@ -0,0 +1,384 @@
+use crate::error::MonochromeManifestError;
+use reqwest::Url;
+use roxmltree::{Document, Node};
+
+/// everything needed to download one representation of a dash manifest, in playback order
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SegmentPlan {
+    pub representation_id: Option<String>,
+    pub bandwidth: Option<u64>,
+    pub codecs: Option<String>,
+    pub mime_type: Option<String>,
+    pub sample_rate: Option<u32>,
+    /// never in the mpd itself, filled in from the track manifest when it says
+    pub bit_depth: Option<u32>,
+    pub initialization: Option<Segment>,
+    pub segments: Vec<Segment>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Segment {
+    pub url: Url,
+    pub range: Option<ByteRange>,
+}
+
+/// an inclusive byte range, as used by http `Range` headers
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ByteRange {
+    pub start: u64,
+    pub end: u64,
+}
+
+impl ByteRange {
+    fn parse(s: &str) -> Option<Self> {
+        let (start, end) = s.split_once('-')?;
+        Some(Self {
+            start: start.trim().parse().ok()?,
+            end: end.trim().parse().ok()?,
+        })
+    }
+
+    pub fn header_value(&self) -> String {
+        format!("bytes={}-{}", self.start, self.end)
+    }
+}
+
+impl Segment {
+    fn new(url: Url, range: Option<&str>) -> Self {
+        Self {
+            url,
+            range: range.and_then(ByteRange::parse),
+        }
+    }
+}
+
+/// parses a dash manifest and plans the download of its highest bandwidth representation.
+/// relative urls are resolved against `BaseURL` elements, falling back to `base`
+pub fn parse(manifest: &str, base: Option<&Url>) -> Result<SegmentPlan, MonochromeManifestError> {
+    let doc = Document::parse(manifest)?;
+    let mpd = doc.root_element();
+    if mpd.tag_name().name() != "MPD" {
+        return Err(MonochromeManifestError::InvalidMpd);
+    }
+
+    let base = with_base_url(base.cloned(), mpd)?;
+
+    let period = child(mpd, "Period").ok_or(MonochromeManifestError::InvalidMpd)?;
+    let base = with_base_url(base, period)?;
+    let period_duration = period
+        .attribute("duration")
+        .or_else(|| mpd.attribute("mediaPresentationDuration"))
+        .and_then(parse_duration);
+
+    let (adaptation_set, representation) = period
+        .children()
+        .filter(|n| n.tag_name().name() == "AdaptationSet")
+        .flat_map(|set| {
+            set.children()
+                .filter(|n| n.tag_name().name() == "Representation")
+                .map(move |rep| (set, rep))
+        })
+        .max_by_key(|(_, rep)| bandwidth(*rep).unwrap_or(0))
+        .ok_or(MonochromeManifestError::MissingRepresentation)?;
+
+    let base = with_base_url(base, adaptation_set)?;
+    let base = with_base_url(base, representation)?;
+
+    // representations inherit these from their adaptation set
+    let inherited = |name| {
+        representation
+            .attribute(name)
+            .or_else(|| adaptation_set.attribute(name))
+    };
+
+    let rep = RepresentationInfo {
+        id: representation.attribute("id").map(str::to_string),
+        bandwidth: bandwidth(representation),
+        codecs: inherited("codecs").map(str::to_string),
+    };
+
+    // segment information is inherited, the most specific level wins
+    let levels = [representation, adaptation_set, period];
+
+    let (initialization, segments) =
+        if let Some(templates) = nonempty(find_all(&levels, "SegmentTemplate")) {
+            plan_template(&templates, &rep, base.as_ref(), period_duration)?
+        } else if let Some(list) = find_all(&levels, "SegmentList").into_iter().next() {
+            plan_list(list, base.as_ref())?
+        } else if find_all(&levels, "SegmentBase")
+            .into_iter()
+            .next()
+            .is_some()
+            || base.is_some()
+        {
+            // SegmentBase (or a bare BaseURL) means the whole resource is one self-initializing file,
+            // so the index and initialization ranges aren't needed to play it back start to finish
+            let url = base.ok_or(MonochromeManifestError::BadBaseUrl)?;
+            (None, vec![Segment::new(url, None)])
+        } else {
+            return Err(MonochromeManifestError::MissingSegmentTemplate);
+        };
+
+    Ok(SegmentPlan {
+        representation_id: rep.id,
+        bandwidth: rep.bandwidth,
+        codecs: rep.codecs,
+        mime_type: inherited("mimeType").map(str::to_string),
+        sample_rate: inherited("audioSamplingRate").and_then(|r| r.parse().ok()),
+        bit_depth: None,
+        initialization,
+        segments,
+    })
+}
+
+struct RepresentationInfo {
+    id: Option<String>,
+    bandwidth: Option<u64>,
+    codecs: Option<String>,
+}
+
+type Planned = (Option<Segment>, Vec<Segment>);
+
+fn plan_template(
+    templates: &[Node],
+    rep: &RepresentationInfo,
+    base: Option<&Url>,
+    period_duration: Option<f64>,
+) -> Result<Planned, MonochromeManifestError> {
+    let attr = |name: &str| templates.iter().find_map(|t| t.attribute(name));
+
+    let media = attr("media").ok_or(MonochromeManifestError::MissingMedia)?;
+    let start_number: u64 = attr("startNumber")
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(1);
+    let timescale: u64 = attr("timescale").and_then(|s| s.parse().ok()).unwrap_or(1);
+    let presentation_offset: u64 = attr("presentationTimeOffset")
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(0);
+
+    let initialization = attr("initialization")
+        .map(|tpl| {
+            let url = expand(tpl, rep, None, None)?;
+            Ok::<_, MonochromeManifestError>(Segment::new(resolve(base, &url)?, None))
+        })
+        .transpose()?;
+
+    let period_end =
+        period_duration.map(|d| presentation_offset + (d * timescale as f64).round() as u64);
+
+    // start time of every segment, in timescale units
+    let mut times: Vec<u64> = Vec::new();
+
+    if let Some(timeline) = templates.iter().find_map(|t| child(*t, "SegmentTimeline")) {
+        let entries = timeline
+            .children()
+            .filter(|c| c.tag_name().name() == "S")
+            .collect::<Vec<_>>();
+
+        // the timeline has its own clock, starting at 0 unless the first S says otherwise. the
+        // presentation offset only says where on that clock playback starts
+        let mut time = 0;
+        for (idx, s) in entries.iter().enumerate() {
+            let d: u64 = s
+                .attribute("d")
+                .and_then(|v| v.parse().ok())
+                .ok_or(MonochromeManifestError::SMissingD)?;
+            if d == 0 {
+                return Err(MonochromeManifestError::SMissingD);
+            }
+
+            if let Some(t) = s.attribute("t").and_then(|v| v.parse().ok()) {
+                time = t;
+            }
+
+            let r: i64 = s.attribute("r").and_then(|v| v.parse().ok()).unwrap_or(0);
+            let count = if r >= 0 {
+                r as u64 + 1
+            } else {
+                // a negative repeat runs until the next S starts, or the end of the period
+                let end = entries
+                    .get(idx + 1)
+                    .and_then(|next| next.attribute("t"))
+                    .and_then(|t| t.parse().ok())
+                    .or(period_end)
+                    .ok_or(MonochromeManifestError::NegativeRepeatUnsupported)?;
+                end.saturating_sub(time).div_ceil(d)
+            };
+
+            for _ in 0..count {
+                times.push(time);
+                time += d;
+            }
+        }
+    } else {
+        let d: u64 = attr("duration")
+            .and_then(|s| s.parse().ok())
+            .filter(|d| *d > 0)
+            .ok_or(MonochromeManifestError::MissingDuration)?;
+        let end = period_end.ok_or(MonochromeManifestError::MissingDuration)?;
+
+        let count = end.saturating_sub(presentation_offset).div_ceil(d);
+        times.extend((0..count).map(|i| presentation_offset + i * d));
+    }
+
+    let segments = times
+        .into_iter()
+        .enumerate()
+        .map(|(idx, time)| {
+            let url = expand(media, rep, Some(start_number + idx as u64), Some(time))?;
+            Ok(Segment::new(resolve(base, &url)?, None))
+        })
+        .collect::<Result<Vec<_>, MonochromeManifestError>>()?;
+
+    Ok((initialization, segments))
+}
+
+fn plan_list(list: Node, base: Option<&Url>) -> Result<Planned, MonochromeManifestError> {
+    let initialization = child(list, "Initialization")
+        .map(|init| {
+            let url = match init.attribute("sourceURL") {
+                Some(src) => resolve(base, src)?,
+                None => base.cloned().ok_or(MonochromeManifestError::BadBaseUrl)?,
+            };
+            Ok::<_, MonochromeManifestError>(Segment::new(url, init.attribute("range")))
+        })
+        .transpose()?;
+
+    let segments = list
+        .children()
+        .filter(|c| c.tag_name().name() == "SegmentURL")
+        .map(|seg| {
+            let url = match seg.attribute("media") {
+                Some(media) => resolve(base, media)?,
+                None => base.cloned().ok_or(MonochromeManifestError::MissingMedia)?,
+            };
+            Ok(Segment::new(url, seg.attribute("mediaRange")))
+        })
+        .collect::<Result<Vec<_>, MonochromeManifestError>>()?;
+
+    if segments.is_empty() {
+        return Err(MonochromeManifestError::MissingMedia);
+    }
+
+    Ok((initialization, segments))
+}
+
+/// fills in `$Number$`, `$Time$`, `$RepresentationID$` and `$Bandwidth$`, including printf style
+/// widths like `$Number%05d$`. `$$` is a literal dollar sign
+fn expand(
+    tpl: &str,
+    rep: &RepresentationInfo,
+    number: Option<u64>,
+    time: Option<u64>,
+) -> Result<String, MonochromeManifestError> {
+    let mut out = String::with_capacity(tpl.len());
+    let mut rest = tpl;
+
+    while let Some(start) = rest.find('$') {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let end = after
+            .find('$')
+            .ok_or_else(|| MonochromeManifestError::BadTemplate(tpl.to_string()))?;
+        let ident = &after[..end];
+        rest = &after[end + 1..];
+
+        if ident.is_empty() {
+            out.push('$');
+            continue;
+        }
+
+        let (name, width) = match ident.split_once('%') {
+            Some((name, fmt)) => {
+                let width = match fmt.strip_suffix('d') {
+                    Some("") => Some(0),
+                    Some(digits) => digits.parse::<usize>().ok(),
+                    None => None,
+                }
+                .ok_or_else(|| MonochromeManifestError::BadTemplate(tpl.to_string()))?;
+                (name, width)
+            }
+            None => (ident, 0),
+        };
+
+        let value = match name {
+            "Number" => number.map(|n| n.to_string()),
+            "Time" => time.map(|t| t.to_string()),
+            "Bandwidth" => rep.bandwidth.map(|b| b.to_string()),
+            "RepresentationID" => rep.id.clone(),
+            _ => None,
+        }
+        .ok_or_else(|| MonochromeManifestError::BadTemplate(tpl.to_string()))?;
+
+        out.push_str(&format!("{value:0>width$}"));
+    }
+
+    out.push_str(rest);
+    Ok(out)
+}
+
+/// parses the subset of iso 8601 durations that manifests use, e.g. `PT3M25.123S`, into seconds
+pub fn parse_duration(s: &str) -> Option<f64> {
+    let s = s.strip_prefix('P')?;
+    let (date, time) = s.split_once('T').unwrap_or((s, ""));
+
+    let mut seconds = 0.0;
+    for (part, units) in [
+        (date, &[('D', 86400.0)][..]),
+        (time, &[('H', 3600.0), ('M', 60.0), ('S', 1.0)][..]),
+    ] {
+        let mut num = String::new();
+        for c in part.chars() {
+            if c.is_ascii_digit() || c == '.' {
+                num.push(c);
+            } else {
+                let (_, scale) = units.iter().find(|(u, _)| *u == c)?;
+                seconds += num.parse::<f64>().ok()? * scale;
+                num.clear();
+            }
+        }
+
+        if !num.is_empty() {
+            return None;
+        }
+    }
+
+    Some(seconds)
+}
+
+fn bandwidth(node: Node) -> Option<u64> {
+    node.attribute("bandwidth").and_then(|b| b.parse().ok())
+}
+
+fn child<'a, 'input>(node: Node<'a, 'input>, name: &str) -> Option<Node<'a, 'input>> {
+    node.children().find(|c| c.tag_name().name() == name)
+}
+
+fn find_all<'a, 'input>(levels: &[Node<'a, 'input>], name: &str) -> Vec<Node<'a, 'input>> {
+    levels
+        .iter()
+        .filter_map(|level| child(*level, name))
+        .collect()
+}
+
+fn nonempty<T>(v: Vec<T>) -> Option<Vec<T>> {
+    (!v.is_empty()).then_some(v)
+}
+
+fn with_base_url(base: Option<Url>, node: Node) -> Result<Option<Url>, MonochromeManifestError> {
+    match child(node, "BaseURL").and_then(|b| b.text()).map(str::trim) {
+        Some(url) if !url.is_empty() => Ok(Some(resolve(base.as_ref(), url)?)),
+        _ => Ok(base),
+    }
+}
+
+fn resolve(base: Option<&Url>, url: &str) -> Result<Url, MonochromeManifestError> {
+    match Url::parse(url) {
+        Ok(url) => Ok(url),
+        Err(url::ParseError::RelativeUrlWithoutBase) => base
+            .ok_or(MonochromeManifestError::BadBaseUrl)?
+            .join(url)
+            .map_err(Into::into),
+        Err(e) => Err(e.into()),
+    }
+}
@@ -0,0 +1,36 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Page<T> {
+    #[serde(default)]
+    pub limit: u32,
+    #[serde(default)]
+    pub offset: Option<u32>,
+    /// `None` when the mirror didn't say how many items there are
+    #[serde(default)]
+    pub total_number_of_items: Option<u32>,
+    pub items: Vec<T>,
+}
+
+impl<T> Page<T> {
+    /// the offset to request after this page, which was requested at `offset`, or `None` if this
+    /// is the last page.
+    ///
+    /// an empty page ends the listing, as does one that starts somewhere other than `offset`,
+    /// since a mirror ignoring the offset would otherwise hand back the same items forever.
+    /// without a total, a page shorter than its limit is the last one
+    pub fn next_offset(&self, offset: u32) -> Option<u32> {
+        if self.items.is_empty() || self.offset.is_some_and(|o| o != offset) {
+            return None;
+        }
+
+        let len = self.items.len() as u32;
+        let next = offset.saturating_add(len);
+
+        match self.total_number_of_items {
+            Some(total) => (next < total).then_some(next),
+            None => (self.limit == 0 || len >= self.limit).then_some(next),
+        }
+    }
+}
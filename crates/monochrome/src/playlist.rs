@@ -0,0 +1,36 @@
+use crate::{id::PlaylistId, track::Track};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct PlaylistCreator {
+    pub id: u64,
+    #[serde(default)]
+    pub name: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Playlist {
+    pub id: PlaylistId,
+    pub title: String,
+    pub description: Option<String>,
+    pub creator: PlaylistCreator,
+    pub cover: Option<Uuid>,
+    pub tracks: Vec<Track>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct PlaylistResult {
+    #[serde(rename = "uuid")]
+    pub id: PlaylistId,
+    pub title: String,
+    pub description: Option<String>,
+    pub creator: PlaylistCreator,
+    #[serde(default, deserialize_with = "crate::null_on_error")]
+    pub square_image: Option<Uuid>,
+    #[serde(default)]
+    pub number_of_tracks: u32,
+}
@@ -0,0 +1,43 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash, Default)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum AudioQuality {
+    #[default]
+    HiResLossless,
+    Lossless,
+    High,
+    Low,
+}
+
+impl AudioQuality {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AudioQuality::HiResLossless => "HI_RES_LOSSLESS",
+            AudioQuality::Lossless => "LOSSLESS",
+            AudioQuality::High => "HIGH",
+            AudioQuality::Low => "LOW",
+        }
+    }
+
+    /// the next tier down, or `None` if this is already the lowest
+    pub fn lower(&self) -> Option<Self> {
+        match self {
+            AudioQuality::HiResLossless => Some(AudioQuality::Lossless),
+            AudioQuality::Lossless => Some(AudioQuality::High),
+            AudioQuality::High => Some(AudioQuality::Low),
+            AudioQuality::Low => None,
+        }
+    }
+
+    /// this tier followed by every tier below it, best first
+    pub fn ladder(self) -> impl Iterator<Item = Self> {
+        std::iter::successors(Some(self), |q| q.lower())
+    }
+}
+
+impl std::fmt::Display for AudioQuality {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
@@ -0,0 +1,247 @@
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use reqwest::{Response, Url, header};
+use serde::{Deserialize, Deserializer};
+use thiserror::Error;
+
+// how long to leave a mirror alone after a 429 that didn't say
+const DEFAULT_RETRY_AFTER: Duration = Duration::from_secs(5);
+const DEFAULT_MAX_BACKOFF: Duration = Duration::from_mins(5);
+
+/// a token bucket: up to `burst` requests at once, refilling at `per_second`
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(try_from = "RawRateLimit")]
+pub struct RateLimit {
+    pub burst: u32,
+    pub per_second: f64,
+}
+
+#[derive(Debug, Error, PartialEq)]
+pub enum InvalidRateLimit {
+    #[error("burst must allow at least one request")]
+    ZeroBurst,
+
+    #[error("per_second must be above zero, got {0}")]
+    Rate(f64),
+}
+
+#[derive(Deserialize)]
+struct RawRateLimit {
+    burst: u32,
+    per_second: f64,
+}
+
+impl RateLimit {
+    /// a bucket with no burst never hands out a token, and one that doesn't refill never hands out
+    /// another, so both are refused rather than left to hang every request
+    pub fn new(burst: u32, per_second: f64) -> Result<Self, InvalidRateLimit> {
+        if burst == 0 {
+            return Err(InvalidRateLimit::ZeroBurst);
+        }
+        if per_second.is_nan() || per_second <= 0.0 {
+            return Err(InvalidRateLimit::Rate(per_second));
+        }
+
+        Ok(Self { burst, per_second })
+    }
+}
+
+impl TryFrom<RawRateLimit> for RateLimit {
+    type Error = InvalidRateLimit;
+
+    fn try_from(raw: RawRateLimit) -> Result<Self, Self::Error> {
+        Self::new(raw.burst, raw.per_second)
+    }
+}
+
+impl Default for RateLimit {
+    fn default() -> Self {
+        Self {
+            burst: 8,
+            per_second: 4.0,
+        }
+    }
+}
+
+/// request budgets for every mirror, with overrides by host for the ones that allow more (or
+/// less) than the rest
+#[derive(Debug, Clone, Deserialize)]
+pub struct RateLimitPolicy {
+    #[serde(default)]
+    pub default: RateLimit,
+    #[serde(default)]
+    pub mirrors: HashMap<String, RateLimit>,
+    /// the longest a 429 keeps a mirror blocked, whatever its `Retry-After` asks for
+    #[serde(
+        default = "default_max_backoff",
+        rename = "max_backoff_secs",
+        deserialize_with = "duration_from_secs"
+    )]
+    pub max_backoff: Duration,
+}
+
+impl Default for RateLimitPolicy {
+    fn default() -> Self {
+        Self {
+            default: RateLimit::default(),
+            mirrors: HashMap::new(),
+            max_backoff: DEFAULT_MAX_BACKOFF,
+        }
+    }
+}
+
+fn default_max_backoff() -> Duration {
+    DEFAULT_MAX_BACKOFF
+}
+
+fn duration_from_secs<'de, D>(deserializer: D) -> Result<Duration, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    u64::deserialize(deserializer).map(Duration::from_secs)
+}
+
+impl RateLimitPolicy {
+    fn limit_for(&self, url: &Url) -> RateLimit {
+        url.host_str()
+            .and_then(|host| self.mirrors.get(host))
+            .copied()
+            .unwrap_or(self.default)
+    }
+}
+
+#[derive(Debug)]
+struct Bucket {
+    limit: RateLimit,
+    tokens: f64,
+    refilled_at: Instant,
+    // set by a 429, nothing goes out before this
+    blocked_until: Option<Instant>,
+}
+
+impl Bucket {
+    fn new(limit: RateLimit) -> Self {
+        Self {
+            limit,
+            tokens: limit.burst as f64,
+            refilled_at: Instant::now(),
+            blocked_until: None,
+        }
+    }
+
+    /// takes a token, or says how long until one is available
+    fn take(&mut self) -> Result<(), Duration> {
+        let now = Instant::now();
+
+        if let Some(until) = self.blocked_until {
+            if now < until {
+                return Err(until - now);
+            }
+            self.blocked_until = None;
+        }
+
+        let elapsed = now.duration_since(self.refilled_at).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.limit.per_second).min(self.limit.burst as f64);
+        self.refilled_at = now;
+
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            Ok(())
+        } else {
+            let missing = 1.0 - self.tokens;
+            Err(Duration::from_secs_f64(
+                missing / self.limit.per_second.max(f64::EPSILON),
+            ))
+        }
+    }
+}
+
+/// one token bucket per mirror, so requests queue up client side instead of finding out about the
+/// limit through 429s
+#[derive(Debug)]
+pub(crate) struct RateLimiter {
+    policy: RateLimitPolicy,
+    buckets: Mutex<HashMap<Url, Bucket>>,
+}
+
+impl RateLimiter {
+    pub(crate) fn new(policy: RateLimitPolicy) -> Self {
+        Self {
+            policy,
+            buckets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// waits until the mirror has budget for another request
+    pub(crate) async fn acquire(&self, url: &Url) {
+        loop {
+            let wait = {
+                let mut buckets = self.buckets.lock().unwrap();
+                let bucket = buckets
+                    .entry(url.clone())
+                    .or_insert_with(|| Bucket::new(self.policy.limit_for(url)));
+
+                match bucket.take() {
+                    Ok(()) => return,
+                    Err(wait) => wait,
+                }
+            };
+
+            tracing::debug!(%url, ?wait, "mirror is out of request budget, waiting");
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    /// whether the mirror asked us to stay away and that hasn't run out yet
+    pub(crate) fn is_blocked(&self, url: &Url) -> bool {
+        self.buckets
+            .lock()
+            .unwrap()
+            .get(url)
+            .and_then(|b| b.blocked_until)
+            .is_some_and(|until| Instant::now() < until)
+    }
+
+    /// stops sending requests to the mirror for however long its 429 said to, up to
+    /// [`RateLimitPolicy::max_backoff`]
+    pub(crate) fn back_off(&self, url: &Url, retry_after: Option<Duration>) {
+        let wait = retry_after
+            .unwrap_or(DEFAULT_RETRY_AFTER)
+            .min(self.policy.max_backoff);
+        tracing::warn!(%url, ?wait, "mirror is rate limiting us, backing off");
+
+        let mut buckets = self.buckets.lock().unwrap();
+        let bucket = buckets
+            .entry(url.clone())
+            .or_insert_with(|| Bucket::new(self.policy.limit_for(url)));
+
+        bucket.tokens = 0.0;
+        // a max_backoff set far enough out can still run past what an Instant holds
+        let now = Instant::now();
+        bucket.blocked_until = Some(
+            now.checked_add(wait)
+                .unwrap_or_else(|| now + DEFAULT_MAX_BACKOFF),
+        );
+    }
+}
+
+/// reads `Retry-After`, which is either a number of seconds or an http date
+pub(crate) fn retry_after(res: &Response) -> Option<Duration> {
+    let value = res
+        .headers()
+        .get(header::RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim();
+
+    if let Ok(secs) = value.parse::<u64>() {
+        return Some(Duration::from_secs(secs));
+    }
+
+    let date = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+    (date.to_utc() - chrono::Utc::now()).to_std().ok()
+}
@@ -0,0 +1,124 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    album::AlbumResult, artist::ArtistDetails, page::Page, playlist::PlaylistResult, track::Track,
+};
+
+const DEFAULT_LIMIT: u32 = 25;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SearchType {
+    Tracks,
+    Albums,
+    Artists,
+    Playlists,
+}
+
+impl SearchType {
+    /// the query parameter the search endpoint expects for this type
+    pub(crate) fn param(&self) -> &'static str {
+        match self {
+            SearchType::Tracks => "s",
+            SearchType::Albums => "al",
+            SearchType::Artists => "a",
+            SearchType::Playlists => "p",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SearchQuery {
+    query: String,
+    kind: SearchType,
+    limit: u32,
+    offset: u32,
+}
+
+impl SearchQuery {
+    pub fn new(query: impl Into<String>) -> Self {
+        Self {
+            query: query.into(),
+            kind: SearchType::Tracks,
+            limit: DEFAULT_LIMIT,
+            offset: 0,
+        }
+    }
+
+    pub fn kind(mut self, kind: SearchType) -> Self {
+        self.kind = kind;
+        self
+    }
+
+    pub fn limit(mut self, limit: u32) -> Self {
+        self.limit = limit;
+        self
+    }
+
+    pub fn offset(mut self, offset: u32) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    pub fn search_type(&self) -> SearchType {
+        self.kind
+    }
+
+    pub fn text(&self) -> &str {
+        &self.query
+    }
+
+    /// the offset and limit of the requested page
+    pub fn page(&self) -> (u32, u32) {
+        (self.offset, self.limit)
+    }
+
+    pub(crate) fn params(&self) -> [(&'static str, String); 3] {
+        [
+            (self.kind.param(), self.query.clone()),
+            ("limit", self.limit.to_string()),
+            ("offset", self.offset.to_string()),
+        ]
+    }
+}
+
+impl<S: Into<String>> From<S> for SearchQuery {
+    fn from(value: S) -> Self {
+        Self::new(value)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum SearchPage {
+    Tracks(Page<Track>),
+    Albums(Page<AlbumResult>),
+    Artists(Page<ArtistDetails>),
+    Playlists(Page<PlaylistResult>),
+}
+
+impl SearchPage {
+    pub fn total(&self) -> Option<u32> {
+        match self {
+            SearchPage::Tracks(p) => p.total_number_of_items,
+            SearchPage::Albums(p) => p.total_number_of_items,
+            SearchPage::Artists(p) => p.total_number_of_items,
+            SearchPage::Playlists(p) => p.total_number_of_items,
+        }
+    }
+
+    pub fn next_offset(&self, offset: u32) -> Option<u32> {
+        match self {
+            SearchPage::Tracks(p) => p.next_offset(offset),
+            SearchPage::Albums(p) => p.next_offset(offset),
+            SearchPage::Artists(p) => p.next_offset(offset),
+            SearchPage::Playlists(p) => p.next_offset(offset),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SearchResults {
+    pub tracks: Page<Track>,
+    pub albums: Page<AlbumResult>,
+    pub artists: Page<ArtistDetails>,
+    pub playlists: Page<PlaylistResult>,
+}
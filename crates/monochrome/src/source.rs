@@ -0,0 +1,163 @@
+use std::{fmt::Debug, path::PathBuf};
+
+use futures::future::BoxFuture;
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+
+use crate::{endpoint::ScanError, timeout::Timeouts};
+
+pub const UPTIME_URL: &str = "https://tidal-uptime.jiffy-puffs-1j.workers.dev";
+
+/// mirrors a source knows about, before they've been measured
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MirrorList {
+    pub api: Vec<Url>,
+    pub streaming: Vec<Url>,
+}
+
+impl MirrorList {
+    pub fn is_empty(&self) -> bool {
+        self.api.is_empty() && self.streaming.is_empty()
+    }
+
+    /// adds the mirrors from `other` that aren't already in the list
+    pub fn merge(&mut self, other: MirrorList) {
+        for url in other.api {
+            if !self.api.contains(&url) {
+                self.api.push(url);
+            }
+        }
+
+        for url in other.streaming {
+            if !self.streaming.contains(&url) {
+                self.streaming.push(url);
+            }
+        }
+    }
+}
+
+/// somewhere [`Endpoint::scan`](crate::endpoint::Endpoint::scan) can find mirrors to measure
+pub trait EndpointSource: Debug + Send + Sync {
+    fn name(&self) -> &str;
+
+    fn mirrors<'a>(
+        &'a self,
+        client: &'a reqwest::Client,
+        timeouts: Timeouts,
+    ) -> BoxFuture<'a, Result<MirrorList, ScanError>>;
+
+    /// called with the mirrors that passed a scan
+    fn remember<'a>(&'a self, _healthy: &'a MirrorList) -> BoxFuture<'a, Result<(), ScanError>> {
+        Box::pin(async { Ok(()) })
+    }
+}
+
+/// the uptime worker that tracks public mirrors
+#[derive(Debug, Clone)]
+pub struct UptimeSource {
+    url: Url,
+}
+
+impl UptimeSource {
+    pub fn new(url: Url) -> Self {
+        Self { url }
+    }
+}
+
+impl Default for UptimeSource {
+    fn default() -> Self {
+        Self::new(UPTIME_URL.parse().unwrap())
+    }
+}
+
+impl EndpointSource for UptimeSource {
+    fn name(&self) -> &str {
+        "uptime"
+    }
+
+    fn mirrors<'a>(
+        &'a self,
+        client: &'a reqwest::Client,
+        timeouts: Timeouts,
+    ) -> BoxFuture<'a, Result<MirrorList, ScanError>> {
+        #[derive(Debug, Deserialize)]
+        struct UptimeResponse {
+            api: Vec<Api>,
+            streaming: Vec<Api>,
+        }
+
+        #[derive(Debug, Deserialize)]
+        struct Api {
+            url: Url,
+        }
+
+        Box::pin(async move {
+            let res = timeouts.send(client.get(self.url.clone())).await?;
+            let uptime: UptimeResponse = timeouts.json(res).await?;
+
+            Ok(MirrorList {
+                api: uptime.api.into_iter().map(|a| a.url).collect(),
+                streaming: uptime.streaming.into_iter().map(|a| a.url).collect(),
+            })
+        })
+    }
+}
+
+/// a fixed list, for self-hosted mirrors or ones the uptime worker doesn't know about
+#[derive(Debug, Clone)]
+pub struct StaticSource(pub MirrorList);
+
+impl EndpointSource for StaticSource {
+    fn name(&self) -> &str {
+        "static"
+    }
+
+    fn mirrors<'a>(
+        &'a self,
+        _client: &'a reqwest::Client,
+        _timeouts: Timeouts,
+    ) -> BoxFuture<'a, Result<MirrorList, ScanError>> {
+        Box::pin(async { Ok(self.0.clone()) })
+    }
+}
+
+/// the mirrors that passed the last successful scan, kept on disk so a restart still has
+/// something to work with when every other source is down
+#[derive(Debug, Clone)]
+pub struct CachedSource {
+    path: PathBuf,
+}
+
+impl CachedSource {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+}
+
+impl EndpointSource for CachedSource {
+    fn name(&self) -> &str {
+        "cache"
+    }
+
+    fn mirrors<'a>(
+        &'a self,
+        _client: &'a reqwest::Client,
+        _timeouts: Timeouts,
+    ) -> BoxFuture<'a, Result<MirrorList, ScanError>> {
+        Box::pin(async {
+            let bytes = tokio::fs::read(&self.path).await?;
+            Ok(serde_json::from_slice(&bytes)?)
+        })
+    }
+
+    fn remember<'a>(&'a self, healthy: &'a MirrorList) -> BoxFuture<'a, Result<(), ScanError>> {
+        Box::pin(async {
+            if let Some(parent) = self.path.parent() {
+                tokio::fs::create_dir_all(parent).await?;
+            }
+
+            tokio::fs::write(&self.path, serde_json::to_vec_pretty(healthy)?).await?;
+            Ok(())
+        })
+    }
+}
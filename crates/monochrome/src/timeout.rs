@@ -0,0 +1,131 @@
+use std::time::Duration;
+
+use async_stream::try_stream;
+use bytes::{Bytes, BytesMut};
+use futures::{Stream, StreamExt};
+use reqwest::{RequestBuilder, Response};
+use serde::de::DeserializeOwned;
+
+use crate::{MonochromeError, RequestKind};
+
+/// how much of an error response is kept. it's only there to be logged, and a misbehaving
+/// mirror can answer with anything
+const MAX_ERROR_BODY: usize = 64 << 10;
+
+/// how long requests are allowed to stall, rather than how long they're allowed to take. a big
+/// flac on a slow link is fine as long as bytes keep arriving
+#[derive(Debug, Clone, Copy)]
+pub struct TimeoutPolicy {
+    /// establishing the tcp + tls connection, shared by every request
+    pub connect: Duration,
+    /// api calls, scans and anything else that returns json
+    pub metadata: Timeouts,
+    /// track bytes, dash segments and artwork
+    pub media: Timeouts,
+}
+
+impl Default for TimeoutPolicy {
+    fn default() -> Self {
+        Self {
+            connect: Duration::from_secs(5),
+            metadata: Timeouts {
+                first_byte: Duration::from_secs(10),
+                read_idle: Duration::from_secs(5),
+            },
+            media: Timeouts {
+                first_byte: Duration::from_secs(15),
+                read_idle: Duration::from_secs(20),
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Timeouts {
+    /// from sending the request until the response headers arrive
+    pub first_byte: Duration,
+    /// the longest gap allowed between two chunks of the body
+    pub read_idle: Duration,
+}
+
+impl Timeouts {
+    pub(crate) async fn send(&self, req: RequestBuilder) -> Result<Response, MonochromeError> {
+        tokio::time::timeout(self.first_byte, req.send())
+            .await
+            .map_err(|_| MonochromeError::FirstByteTimeout(self.first_byte))?
+            .map_err(MonochromeError::from)
+    }
+
+    pub(crate) fn body(self, res: Response) -> impl Stream<Item = Result<Bytes, MonochromeError>> {
+        let read_idle = self.read_idle;
+
+        try_stream! {
+            let mut body = res.bytes_stream();
+            while let Some(chunk) = tokio::time::timeout(read_idle, body.next())
+                .await
+                .map_err(|_| MonochromeError::ReadIdleTimeout(read_idle))?
+            {
+                yield chunk?;
+            }
+        }
+    }
+
+    pub(crate) async fn bytes(&self, res: Response) -> Result<Bytes, MonochromeError> {
+        // the length is the server's word, so it only gets to reserve so much up front
+        let capacity = res.content_length().unwrap_or(0).min(1 << 20);
+        let mut buf = BytesMut::with_capacity(capacity as usize);
+        let mut body = std::pin::pin!(self.body(res));
+
+        while let Some(chunk) = body.next().await {
+            buf.extend_from_slice(&chunk?);
+        }
+
+        Ok(buf.freeze())
+    }
+
+    /// passes a successful response through, and turns any other into a
+    /// [`MonochromeError::Status`] with as much of the body as arrives in time, up to
+    /// [`MAX_ERROR_BODY`]
+    pub(crate) async fn check(
+        &self,
+        res: Response,
+        kind: RequestKind,
+    ) -> Result<Response, MonochromeError> {
+        let status = res.status();
+        if status.is_success() {
+            return Ok(res);
+        }
+
+        let mut endpoint = res.url().clone();
+        endpoint.set_query(None);
+
+        Err(MonochromeError::Status {
+            status,
+            endpoint: endpoint.to_string(),
+            kind,
+            body: String::from_utf8_lossy(&self.head(res, MAX_ERROR_BODY).await).into_owned(),
+        })
+    }
+
+    /// the first `limit` bytes of the body, or whatever arrived before it failed
+    async fn head(&self, res: Response, limit: usize) -> Bytes {
+        let mut buf = BytesMut::new();
+        let mut body = std::pin::pin!(self.body(res));
+
+        while buf.len() < limit
+            && let Some(Ok(chunk)) = body.next().await
+        {
+            let take = chunk.len().min(limit - buf.len());
+            buf.extend_from_slice(&chunk[..take]);
+        }
+
+        buf.freeze()
+    }
+
+    pub(crate) async fn json<T: DeserializeOwned>(
+        &self,
+        res: Response,
+    ) -> Result<T, MonochromeError> {
+        Ok(serde_json::from_slice(&self.bytes(res).await?)?)
+    }
+}
@@ -1,6 +1,10 @@
 use crate::{
+    MonochromeError,
     artist::Artist,
     id::{AlbumId, TrackId},
+    manifest::{BtsManifest, Manifest, ManifestKind},
+    mpd,
+    quality::AudioQuality,
 };
 use base64::{Engine, prelude::BASE64_STANDARD};
 use chrono::{DateTime, Utc};
@@ -12,8 +16,15 @@ use uuid::Uuid;
 pub struct TrackManifest {
     pub track_id: TrackId,
     pub asset_presentation: String,
-    pub manifest_mime_type: String,
+    pub manifest_mime_type: ManifestKind,
     pub manifest: String,
+    /// the tier the mirror actually served. filled in with the requested tier if the mirror omits it
+    #[serde(default)]
+    pub audio_quality: Option<AudioQuality>,
+    #[serde(default)]
+    pub bit_depth: Option<u32>,
+    #[serde(default)]
+    pub sample_rate: Option<u32>,
 }
 
 impl TrackManifest {
@@ -21,6 +32,30 @@ impl TrackManifest {
         let decoded = BASE64_STANDARD.decode(&self.manifest)?;
         Ok(String::from_utf8_lossy(&decoded).into())
     }
+
+    /// decodes the manifest according to its mime type
+    pub fn parse(&self) -> Result<Manifest, MonochromeError> {
+        let decoded = self.decode_manifest()?;
+
+        match &self.manifest_mime_type {
+            ManifestKind::Dash => {
+                let mut plan = mpd::parse(&decoded, None)?;
+                plan.bit_depth = self.bit_depth;
+                plan.sample_rate = self.sample_rate.or(plan.sample_rate);
+                Ok(Manifest::Dash(plan))
+            }
+            ManifestKind::Bts => {
+                let mut bts: BtsManifest =
+                    serde_json::from_str(&decoded).map_err(|_| MonochromeError::ManifestDecode)?;
+                bts.bit_depth = self.bit_depth;
+                bts.sample_rate = self.sample_rate;
+                Ok(Manifest::Bts(bts))
+            }
+            ManifestKind::Other(mime) => {
+                Err(MonochromeError::UnsupportedManifestMimeType(mime.clone()))
+            }
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -0,0 +1,28 @@
+use monochrome::art::ArtSize;
+
+#[test]
+fn fallbacks_go_down_then_up() {
+    let sizes = ArtSize::Px640.fallbacks().collect::<Vec<_>>();
+
+    assert_eq!(
+        sizes,
+        [
+            ArtSize::Px640,
+            ArtSize::Px480,
+            ArtSize::Px320,
+            ArtSize::Px160,
+            ArtSize::Px80,
+            ArtSize::Px750,
+            ArtSize::Px1080,
+            ArtSize::Px1280,
+            ArtSize::Origin,
+        ]
+    );
+}
+
+#[test]
+fn sizes_map_to_file_names() {
+    assert_eq!(ArtSize::default().file_name(), "1280x1280.jpg");
+    assert_eq!(ArtSize::Origin.file_name(), "origin.jpg");
+    assert_eq!(ArtSize::Px750.to_string(), "750");
+}
@@ -0,0 +1,167 @@
+use std::{path::PathBuf, time::Duration};
+
+use monochrome::{
+    Monochrome,
+    cache::CacheConfig,
+    mock::{MockServer, sample_album},
+};
+
+fn scratch_dir() -> PathBuf {
+    std::env::temp_dir().join(format!("monochrome-cache-{}", uuid::Uuid::new_v4()))
+}
+
+fn cached(server: &MockServer, config: CacheConfig) -> Monochrome {
+    server.endpoint().api().with_cache(config)
+}
+
+// the same hash the cache names its files with
+fn fnv1a(s: &str) -> u64 {
+    s.bytes().fold(0xcbf29ce484222325, |hash, b| {
+        (hash ^ b as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+#[tokio::test]
+async fn serves_repeats_from_memory() {
+    let server = MockServer::start().await.unwrap();
+    server.data("/album?id=1", sample_album(1, &[10]));
+
+    let monochrome = cached(&server, CacheConfig::default());
+    monochrome.album(1).await.unwrap();
+    monochrome.album(1).await.unwrap();
+
+    assert_eq!(server.hits("/album"), 1);
+}
+
+#[tokio::test]
+async fn refetches_once_expired() {
+    let server = MockServer::start().await.unwrap();
+    server.data("/album?id=1", sample_album(1, &[10]));
+
+    let monochrome = cached(
+        &server,
+        CacheConfig {
+            album_ttl: Duration::from_millis(50),
+            ..Default::default()
+        },
+    );
+    monochrome.album(1).await.unwrap();
+    monochrome.album(1).await.unwrap();
+    assert_eq!(server.hits("/album"), 1);
+
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    monochrome.album(1).await.unwrap();
+    assert_eq!(server.hits("/album"), 2);
+}
+
+#[tokio::test]
+async fn evicts_the_least_recently_used() {
+    let server = MockServer::start().await.unwrap();
+    for id in 1..=3 {
+        server.data(&format!("/album?id={id}"), sample_album(id, &[10]));
+    }
+
+    let monochrome = cached(
+        &server,
+        CacheConfig {
+            capacity: 2,
+            ..Default::default()
+        },
+    );
+    monochrome.album(1).await.unwrap();
+    monochrome.album(2).await.unwrap();
+    // 1 is now more recent than 2, so 3 pushes 2 out
+    monochrome.album(1).await.unwrap();
+    monochrome.album(3).await.unwrap();
+    assert_eq!(server.hits("/album"), 3);
+
+    monochrome.album(1).await.unwrap();
+    assert_eq!(server.hits("/album"), 3);
+    monochrome.album(2).await.unwrap();
+    assert_eq!(server.hits("/album"), 4);
+}
+
+#[tokio::test]
+async fn replacing_an_entry_evicts_nothing() {
+    let server = MockServer::start().await.unwrap();
+    server.data("/album?id=1", sample_album(1, &[10]));
+    server.data(
+        "/track?id=10",
+        sample_album(1, &[10])["items"][0]["item"].clone(),
+    );
+
+    let monochrome = cached(
+        &server,
+        CacheConfig {
+            capacity: 2,
+            ..Default::default()
+        },
+    );
+    monochrome.track(10).await.unwrap();
+
+    // both miss, so the second to finish replaces the first's entry
+    let (first, second) = tokio::join!(monochrome.album(1), monochrome.album(1));
+    first.unwrap();
+    second.unwrap();
+    assert_eq!(server.hits("/album"), 2);
+
+    // which leaves room for the older track
+    monochrome.track(10).await.unwrap();
+    assert_eq!(server.hits("/track"), 1);
+}
+
+#[tokio::test]
+async fn survives_a_restart_on_disk() {
+    let dir = scratch_dir();
+    let config = CacheConfig {
+        dir: Some(dir.clone()),
+        ..Default::default()
+    };
+
+    let server = MockServer::start().await.unwrap();
+    server.data("/album?id=1", sample_album(1, &[10, 11]));
+    let fetched = cached(&server, config.clone()).album(1).await.unwrap();
+    assert!(
+        dir.join("album")
+            .join(format!("{:016x}.json", fnv1a("1")))
+            .exists()
+    );
+
+    // a fresh client with an empty memory cache, against a server that has nothing
+    let empty = MockServer::start().await.unwrap();
+    let restored = cached(&empty, config).album(1).await.unwrap();
+
+    assert_eq!(restored.title, fetched.title);
+    assert_eq!(restored.tracks.len(), 2);
+    assert_eq!(empty.hits("/album"), 0);
+
+    std::fs::remove_dir_all(dir).ok();
+}
+
+#[tokio::test]
+async fn ignores_a_file_holding_another_key() {
+    let dir = scratch_dir();
+    let config = CacheConfig {
+        dir: Some(dir.clone()),
+        ..Default::default()
+    };
+
+    let server = MockServer::start().await.unwrap();
+    server.data("/album?id=1", sample_album(1, &[10]));
+    server.data("/album?id=2", sample_album(2, &[20]));
+    cached(&server, config.clone()).album(1).await.unwrap();
+
+    // stand in for a hash collision by putting album 1 where album 2 would go
+    let album = dir.join("album");
+    std::fs::copy(
+        album.join(format!("{:016x}.json", fnv1a("1"))),
+        album.join(format!("{:016x}.json", fnv1a("2"))),
+    )
+    .unwrap();
+
+    let fetched = cached(&server, config).album(2).await.unwrap();
+    assert_eq!(fetched.id, 2.into());
+    assert_eq!(server.hits("/album"), 2);
+
+    std::fs::remove_dir_all(dir).ok();
+}
@@ -0,0 +1,65 @@
+use std::sync::Arc;
+
+use bytes::Bytes;
+use futures::TryStreamExt;
+use monochrome::{mock::MockServer, quality::AudioQuality};
+use serde_json::json;
+use tokio::sync::Semaphore;
+
+// worked out with openssl rather than this crate, so both halves are checked against something
+// independent:
+//
+//   key   = 000102030405060708090a0b0c0d0e0f
+//   nonce = a0a1a2a3a4a5a6a7
+//   iv    = f0f1f2f3f4f5f6f7f8f9fafbfcfdfeff
+//
+//   openssl enc -aes-256-cbc -nopad -K <master key> -iv <iv>   on key || nonce || 8 zero bytes
+//   openssl enc -aes-128-ctr -K <key> -iv <nonce>0000000000000000   on PLAIN
+const KEY_ID: &str = "8PHy8/T19vf4+fr7/P3+/28UsxprwhfyoUrLzBrG1uxv+lkG1p743ifnxffI3rxV";
+
+const PLAIN: &[u8] = b"the quick brown fox jumps over the lazy dog, twice: the quick brown fox jumps over the lazy dog. done";
+
+const CIPHER: &str = "af40f1bc4fd8d4030c2b9e9d421717ef06894aba3d11cbc668c6208d5ba69b1e\
+                      d04a0d11ee577fee779833f26f0b0cfc52ad7e2c88e4e0aa39ca52c0df0d8305\
+                      9648244c8a70640789bd78deda3c3f1a9fe7f1628bc5797b93e458b7475b04e4\
+                      6208c4ca3c";
+
+fn unhex(s: &str) -> Vec<u8> {
+    (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap())
+        .collect()
+}
+
+#[tokio::test]
+async fn decrypts_a_known_answer() {
+    let server = MockServer::start().await.unwrap();
+    let manifest = json!({
+        "mimeType": "audio/flac",
+        "codecs": "flac",
+        "encryptionType": "OLD_AES",
+        "keyId": KEY_ID,
+        "urls": [server.url().join("files/1.flac").unwrap()],
+    });
+    server.manifest(
+        1,
+        "application/vnd.tidal.bts",
+        manifest.to_string().as_bytes(),
+    );
+    server.respond("/files/1.flac", 200, "audio/flac", unhex(CIPHER));
+
+    let monochrome = server.endpoint().api();
+    let manifest = monochrome
+        .track_manifest(1, AudioQuality::Lossless)
+        .await
+        .unwrap();
+    let chunks: Vec<Bytes> = monochrome
+        .download_track(&manifest, Arc::new(Semaphore::new(1)))
+        .await
+        .unwrap()
+        .try_collect()
+        .await
+        .unwrap();
+
+    assert_eq!(chunks.concat(), PLAIN);
+}
@@ -0,0 +1,128 @@
+use std::{path::PathBuf, sync::Arc};
+
+use bytes::Bytes;
+use futures::TryStreamExt;
+use monochrome::{
+    MonochromeError,
+    endpoint::Endpoint,
+    fixture::FixtureMode,
+    mock::{MockServer, sample_album},
+    quality::AudioQuality,
+    source::MirrorList,
+};
+use tokio::sync::Semaphore;
+
+fn fixtures(name: &str) -> PathBuf {
+    PathBuf::from(format!(
+        "{}/tests/fixtures/{name}",
+        env!("CARGO_MANIFEST_DIR")
+    ))
+}
+
+fn scratch_dir() -> PathBuf {
+    std::env::temp_dir().join(format!("monochrome-fixtures-{}", uuid::Uuid::new_v4()))
+}
+
+#[tokio::test]
+async fn replays_what_was_recorded() {
+    let dir = scratch_dir();
+    let server = MockServer::start().await.unwrap();
+    server.data("/album?id=1", sample_album(1, &[10, 11]));
+
+    let recorded = server
+        .endpoint()
+        .with_fixtures(FixtureMode::Record(dir.clone()))
+        .api()
+        .album(1)
+        .await
+        .unwrap();
+    drop(server);
+
+    // nothing is listening anymore, so this can only come from the recording
+    let replayed = Endpoint::new()
+        .with_fixtures(FixtureMode::Replay(dir.clone()))
+        .api()
+        .album(1)
+        .await
+        .unwrap();
+
+    assert!(dir.join("album").join("id=1.json").exists());
+    assert_eq!(replayed.title, recorded.title);
+    assert_eq!(replayed.tracks.len(), 2);
+
+    std::fs::remove_dir_all(dir).ok();
+}
+
+#[tokio::test]
+async fn replays_a_checked_in_fixture() {
+    let album = Endpoint::new()
+        .with_fixtures(FixtureMode::Replay(fixtures("replay")))
+        .api()
+        .album(75413011)
+        .await
+        .unwrap();
+
+    assert_eq!(album.kind, "EP");
+    assert_eq!(album.tracks.len(), 1);
+    assert_eq!(album.tracks[0].title, "Retrograde (Remix)");
+}
+
+#[tokio::test]
+async fn missing_fixture_is_an_error() {
+    let err = Endpoint::new()
+        .with_fixtures(FixtureMode::Replay(fixtures("replay")))
+        .api()
+        .album(1)
+        .await
+        .unwrap_err();
+
+    assert!(matches!(err, MonochromeError::MissingFixture(request) if request == "album?id=1"));
+}
+
+#[tokio::test]
+async fn replays_manifests_for_other_mirrors() {
+    let dir = scratch_dir();
+    let segments = (1..=4u8)
+        .map(|i| Bytes::from(vec![i; 256]))
+        .collect::<Vec<_>>();
+    let original = MockServer::start().await.unwrap();
+    let other = MockServer::start().await.unwrap();
+    for server in [&original, &other] {
+        server.dash_track(2, vec![0u8; 64], segments.clone());
+    }
+
+    original
+        .endpoint()
+        .with_fixtures(FixtureMode::Record(dir.clone()))
+        .api()
+        .track_manifest(2, AudioQuality::Lossless)
+        .await
+        .unwrap();
+
+    // spreading segments asks the other mirror for its manifest, which has to be replayed too
+    let monochrome = Endpoint::new()
+        .with_mirrors(MirrorList {
+            api: vec![original.url(), other.url()],
+            streaming: vec![original.url(), other.url()],
+        })
+        .with_fixtures(FixtureMode::Replay(dir.clone()))
+        .api()
+        .with_segment_fanout(2);
+    let manifest = monochrome
+        .track_manifest(2, AudioQuality::Lossless)
+        .await
+        .unwrap();
+    let bytes: Vec<Bytes> = monochrome
+        .download_track(&manifest, Arc::new(Semaphore::new(4)))
+        .await
+        .unwrap()
+        .try_collect()
+        .await
+        .unwrap();
+
+    assert_eq!(bytes.concat(), [vec![0u8; 64], segments.concat()].concat());
+    assert_eq!(original.hits("/track"), 1);
+    assert_eq!(other.hits("/track"), 0);
+
+    std::fs::remove_dir_all(dir).ok();
+}
@@ -0,0 +1,139 @@
+use monochrome::id::{ParseTidalRefError, TidalRef};
+use reqwest::Url;
+use uuid::Uuid;
+
+const PLAYLIST: &str = "36ea71a8-445e-41a4-82ab-6628c581535d";
+
+fn playlist() -> TidalRef {
+    TidalRef::Playlist(PLAYLIST.parse::<Uuid>().unwrap().into())
+}
+
+#[test]
+fn parses_share_urls() {
+    let cases = [
+        // browse links, for every kind
+        (
+            "https://tidal.com/browse/track/1",
+            TidalRef::Track(1.into()),
+        ),
+        (
+            "https://tidal.com/browse/album/2",
+            TidalRef::Album(2.into()),
+        ),
+        (
+            "https://tidal.com/browse/artist/3",
+            TidalRef::Artist(3.into()),
+        ),
+        (
+            &format!("https://tidal.com/browse/playlist/{PLAYLIST}"),
+            playlist(),
+        ),
+        (
+            "https://tidal.com/browse/video/5",
+            TidalRef::Video(5.into()),
+        ),
+        // the web player
+        (
+            "https://listen.tidal.com/track/1",
+            TidalRef::Track(1.into()),
+        ),
+        (
+            "https://listen.tidal.com/album/2",
+            TidalRef::Album(2.into()),
+        ),
+        (
+            "https://listen.tidal.com/artist/3",
+            TidalRef::Artist(3.into()),
+        ),
+        (
+            &format!("https://listen.tidal.com/playlist/{PLAYLIST}"),
+            playlist(),
+        ),
+        // share suffixes and tracking queries
+        (
+            "https://tidal.com/browse/track/1/u",
+            TidalRef::Track(1.into()),
+        ),
+        (
+            "https://tidal.com/track/1/u?utm_source=x",
+            TidalRef::Track(1.into()),
+        ),
+        (
+            "https://tidal.com/browse/album/2?play=true",
+            TidalRef::Album(2.into()),
+        ),
+        (
+            "https://listen.tidal.com/album/2/track/1",
+            TidalRef::Track(1.into()),
+        ),
+        (
+            "http://www.tidal.com/browse/albums/2",
+            TidalRef::Album(2.into()),
+        ),
+        (
+            "HTTPS://TIDAL.COM/BROWSE/TRACK/1",
+            TidalRef::Track(1.into()),
+        ),
+        // without a scheme, as an app link, or as just the kind and id
+        ("tidal.com/browse/track/1", TidalRef::Track(1.into())),
+        ("listen.tidal.com/album/2", TidalRef::Album(2.into())),
+        ("tidal://track/1", TidalRef::Track(1.into())),
+        ("tidal://album/2", TidalRef::Album(2.into())),
+        ("track/1", TidalRef::Track(1.into())),
+        ("album/2/", TidalRef::Album(2.into())),
+        (&format!("playlist/{PLAYLIST}"), playlist()),
+        (
+            "  https://tidal.com/browse/artist/3  ",
+            TidalRef::Artist(3.into()),
+        ),
+    ];
+
+    for (input, expected) in cases {
+        let parsed: TidalRef = input
+            .parse()
+            .unwrap_or_else(|e| panic!("{input} failed to parse: {e}"));
+        assert_eq!(parsed, expected, "{input}");
+    }
+}
+
+#[test]
+fn parses_urls_directly() {
+    let url = Url::parse("https://listen.tidal.com/video/5").unwrap();
+    assert_eq!(TidalRef::try_from(&url).unwrap(), TidalRef::Video(5.into()));
+}
+
+#[test]
+fn rejects_everything_else() {
+    let cases = [
+        "https://open.spotify.com/track/1",
+        "https://nottidal.com/browse/track/1",
+        "ftp://tidal.com/browse/track/1",
+        "https://tidal.com/browse",
+        "https://tidal.com/browse/mix/abc",
+        "https://tidal.com/browse/track/abc",
+        "https://tidal.com/browse/playlist/123",
+        "track/-1",
+        "123",
+        "",
+    ];
+
+    for input in cases {
+        assert!(
+            input.parse::<TidalRef>().is_err(),
+            "{input} should not parse"
+        );
+    }
+
+    assert!(matches!(
+        "https://example.com/track/1".parse::<TidalRef>(),
+        Err(ParseTidalRefError::NotTidal)
+    ));
+    assert!(matches!(
+        "https://tidal.com/browse".parse::<TidalRef>(),
+        Err(ParseTidalRefError::MissingRef)
+    ));
+    assert!(matches!(
+        "https://tidal.com/browse/album/x".parse::<TidalRef>(),
+        Err(ParseTidalRefError::InvalidId(id)) if id == "x"
+    ));
+}
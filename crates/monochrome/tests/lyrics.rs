@@ -0,0 +1,86 @@
+use std::time::Duration;
+
+use monochrome::{
+    id::TrackId,
+    lyrics::{Lyrics, SyncedLine, parse_lrc},
+};
+
+fn line(millis: u64, text: &str) -> SyncedLine {
+    SyncedLine {
+        time: Duration::from_millis(millis),
+        text: text.to_string(),
+    }
+}
+
+#[test]
+fn parses_timestamps_and_skips_tags() {
+    let lrc = "[ar:someone]\n[ti:something]\n\n[00:01.5] tenths\n[00:02.25]hundredths\n[01:03.125] millis  \n[00:04] no fraction\nnot a line";
+
+    assert_eq!(
+        parse_lrc(lrc),
+        vec![
+            line(1500, "tenths"),
+            line(2250, "hundredths"),
+            line(4000, "no fraction"),
+            line(63125, "millis"),
+        ]
+    );
+}
+
+#[test]
+fn repeats_lines_with_several_timestamps() {
+    let lrc = "[00:01.00][00:05.00]chorus\n[00:03.00] verse";
+
+    assert_eq!(
+        parse_lrc(lrc),
+        vec![
+            line(1000, "chorus"),
+            line(3000, "verse"),
+            line(5000, "chorus")
+        ]
+    );
+}
+
+#[test]
+fn rejects_timestamps_that_overflow() {
+    let lrc = "[307445734561825861:00.00] too late\n[00:01.00][307445734561825861:00.00] also too late\n[00:02.00] fine";
+
+    assert_eq!(parse_lrc(lrc), vec![line(2000, "fine")]);
+}
+
+#[test]
+fn keeps_empty_lines_between_verses() {
+    assert_eq!(parse_lrc("[00:10.00]"), vec![line(10000, "")]);
+}
+
+#[test]
+fn round_trips_through_lrc() {
+    let lyrics = Lyrics {
+        track_id: TrackId::from(1),
+        provider: None,
+        plain: None,
+        synced: vec![
+            line(1230, "first"),
+            line(61000, "second"),
+            line(3_600_000, "late"),
+        ],
+        right_to_left: false,
+    };
+
+    let lrc = lyrics.to_lrc().unwrap();
+    assert_eq!(lrc, "[00:01.23]first\n[01:01.00]second\n[60:00.00]late\n");
+    assert_eq!(parse_lrc(&lrc), lyrics.synced);
+}
+
+#[test]
+fn no_lrc_without_synced_lines() {
+    let lyrics = Lyrics {
+        track_id: TrackId::from(1),
+        provider: None,
+        plain: Some("just words".to_string()),
+        synced: Vec::new(),
+        right_to_left: false,
+    };
+
+    assert_eq!(lyrics.to_lrc(), None);
+}
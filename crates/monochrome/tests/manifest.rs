@@ -0,0 +1,115 @@
+use base64::{Engine, prelude::BASE64_STANDARD};
+use monochrome::{
+    MonochromeError,
+    manifest::{Encryption, Manifest, ManifestKind, StreamInfo},
+    track::TrackManifest,
+};
+use serde_json::json;
+
+fn track_manifest(mime: &str, manifest: &str) -> TrackManifest {
+    serde_json::from_value(json!({
+        "trackId": 1,
+        "assetPresentation": "FULL",
+        "manifestMimeType": mime,
+        "manifest": BASE64_STANDARD.encode(manifest),
+        "bitDepth": 24,
+        "sampleRate": 96000,
+    }))
+    .unwrap()
+}
+
+/// a track manifest from a mirror that leaves out the bit depth and sample rate
+fn bare_track_manifest(mime: &str, manifest: &str) -> TrackManifest {
+    let mut manifest = track_manifest(mime, manifest);
+    manifest.bit_depth = None;
+    manifest.sample_rate = None;
+    manifest
+}
+
+fn fixture(name: &str) -> String {
+    let path = format!("{}/tests/fixtures/mpd/{name}", env!("CARGO_MANIFEST_DIR"));
+    std::fs::read_to_string(&path).unwrap_or_else(|e| panic!("failed to read {path}: {e}"))
+}
+
+#[test]
+fn dash_stream_info() {
+    let manifest = track_manifest(
+        "application/dash+xml",
+        &fixture("tidal_number_timeline.mpd"),
+    );
+    assert_eq!(manifest.manifest_mime_type, ManifestKind::Dash);
+
+    let parsed = manifest.parse().unwrap();
+    assert!(matches!(parsed, Manifest::Dash(_)));
+
+    // the track manifest's bitDepth and sampleRate win over the representation id
+    let info = parsed.stream_info();
+    assert_eq!(
+        info,
+        StreamInfo {
+            codec: Some("flac".into()),
+            container: Some("mp4".into()),
+            bit_depth: Some(24),
+            sample_rate: Some(96000),
+            encryption: Encryption::None,
+        }
+    );
+    assert_eq!(info.to_string(), "FLAC 24-bit/96 kHz");
+}
+
+#[test]
+fn dash_stream_info_from_the_representation() {
+    let manifest = bare_track_manifest(
+        "application/dash+xml",
+        &fixture("tidal_number_timeline.mpd"),
+    );
+
+    let info = manifest.parse().unwrap().stream_info();
+    assert_eq!(info.bit_depth, Some(16));
+    assert_eq!(info.sample_rate, Some(44100));
+    assert_eq!(info.to_string(), "FLAC 16-bit/44.1 kHz");
+}
+
+#[test]
+fn bts_stream_info() {
+    let bts = json!({
+        "mimeType": "audio/mp4",
+        "codecs": "mp4a.40.2",
+        "encryptionType": "OLD_AES",
+        "keyId": "a2V5",
+        "urls": ["https://example.com/track.m4a"],
+    });
+    let manifest = track_manifest("application/vnd.tidal.bts", &bts.to_string());
+
+    let Manifest::Bts(parsed) = manifest.parse().unwrap() else {
+        panic!("expected a bts manifest");
+    };
+    assert_eq!(parsed.key_id.as_deref(), Some("a2V5"));
+    assert_eq!(parsed.urls.len(), 1);
+
+    assert_eq!(parsed.bit_depth, Some(24));
+    assert_eq!(parsed.sample_rate, Some(96000));
+
+    let info = Manifest::Bts(parsed).stream_info();
+    assert_eq!(info.encryption, Encryption::OldAes);
+    assert_eq!(info.container.as_deref(), Some("mp4"));
+    assert_eq!(info.bit_depth, Some(24));
+    assert_eq!(info.sample_rate, Some(96000));
+    assert_eq!(info.to_string(), "AAC 24-bit/96 kHz (encrypted)");
+}
+
+#[test]
+fn unknown_mime_type() {
+    let manifest = track_manifest("application/vnd.apple.mpegurl", "#EXTM3U");
+    assert_eq!(
+        manifest.manifest_mime_type,
+        ManifestKind::Other("application/vnd.apple.mpegurl".into())
+    );
+
+    match manifest.parse() {
+        Err(MonochromeError::UnsupportedManifestMimeType(mime)) => {
+            assert_eq!(mime, "application/vnd.apple.mpegurl")
+        }
+        other => panic!("expected an unsupported mime type, got {other:?}"),
+    }
+}
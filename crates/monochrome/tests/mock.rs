@@ -0,0 +1,716 @@
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use bytes::Bytes;
+use futures::TryStreamExt;
+use monochrome::{
+    Monochrome, MonochromeError, MonochromeManifestError, RequestKind,
+    download::SegmentRetryPolicy,
+    manifest::ManifestKind,
+    mock::{Fault, MockServer, sample_album},
+    quality::AudioQuality,
+    ratelimit::{RateLimit, RateLimitPolicy},
+    search::{SearchQuery, SearchType},
+    source::MirrorList,
+    timeout::{TimeoutPolicy, Timeouts},
+};
+use tokio::sync::Semaphore;
+
+fn quick_retries() -> SegmentRetryPolicy {
+    SegmentRetryPolicy {
+        initial_backoff: Duration::from_millis(10),
+        max_backoff: Duration::from_millis(50),
+        ..Default::default()
+    }
+}
+
+async fn download(monochrome: &Monochrome, track_id: u64) -> (Vec<u8>, u32, u32) {
+    let manifest = monochrome
+        .track_manifest(track_id, AudioQuality::Lossless)
+        .await
+        .unwrap();
+
+    let stream = monochrome
+        .download_track(&manifest, Arc::new(Semaphore::new(4)))
+        .await
+        .unwrap();
+    let stats = stream.stats();
+
+    let chunks: Vec<Bytes> = stream.try_collect().await.unwrap();
+    (chunks.concat(), stats.segment_retries(), stats.resumes())
+}
+
+#[tokio::test]
+async fn fails_over_to_a_healthy_mirror() {
+    let broken = MockServer::start().await.unwrap();
+    let healthy = MockServer::start().await.unwrap();
+
+    broken.respond("/album?id=1", 503, "text/plain", "down for maintenance");
+    healthy.data("/album?id=1", sample_album(1, &[10, 11]));
+
+    let endpoint = broken.endpoint().with_mirrors(MirrorList {
+        api: vec![broken.url(), healthy.url()],
+        streaming: vec![healthy.url()],
+    });
+
+    let album = endpoint.api().album(1).await.unwrap();
+
+    assert_eq!(album.tracks.len(), 2);
+    assert_eq!(healthy.hits("/album"), 1);
+}
+
+#[tokio::test]
+async fn failing_requests_share_one_rescan() {
+    let server = MockServer::start().await.unwrap();
+    server.respond("/album?id=1", 503, "text/plain", "down for maintenance");
+
+    let monochrome = server.endpoint().api();
+    let results = futures::future::join_all((0..8).map(|_| monochrome.album(1))).await;
+
+    assert!(results.iter().all(Result::is_err));
+    assert_eq!(server.hits("/uptime"), 1);
+}
+
+/// a page of artist albums, the way mirrors wrap them. `offset` and `total` are left out when
+/// `None`, like some mirrors do
+fn album_page(
+    ids: std::ops::Range<u64>,
+    offset: Option<u64>,
+    total: Option<u64>,
+) -> serde_json::Value {
+    let mut page = serde_json::json!({
+        "limit": 100,
+        "items": ids.map(|id| sample_album(id, &[])).collect::<Vec<_>>(),
+    });
+    if let Some(offset) = offset {
+        page["offset"] = offset.into();
+    }
+    if let Some(total) = total {
+        page["totalNumberOfItems"] = total.into();
+    }
+    page
+}
+
+#[tokio::test]
+async fn pages_through_a_discography() {
+    let server = MockServer::start().await.unwrap();
+    server.data(
+        "/artist?id=1",
+        serde_json::json!({ "id": 1, "name": "mock artist", "picture": null }),
+    );
+    server.data(
+        "/artist/albums?id=1&filter=ALBUMS&offset=0",
+        album_page(1000..1100, Some(0), Some(150)),
+    );
+    server.data(
+        "/artist/albums?id=1&filter=ALBUMS&offset=100",
+        album_page(1100..1150, Some(100), Some(150)),
+    );
+    // no total, so the short page is the last one
+    server.data(
+        "/artist/albums?id=1&filter=EPSANDSINGLES&offset=0",
+        album_page(2000..2002, None, None),
+    );
+    // a mirror that ignores the offset and hands back the first page every time
+    server.data(
+        "/artist/albums?id=1&filter=COMPILATIONS",
+        album_page(3000..3100, Some(0), None),
+    );
+
+    let discography = server.endpoint().api().artist(1).await.unwrap();
+
+    let ids = |albums: &[monochrome::album::AlbumResult]| {
+        albums.iter().map(|a| a.id.to_string()).collect::<Vec<_>>()
+    };
+    assert_eq!(
+        ids(&discography.albums),
+        (1000..1150).map(|id| id.to_string()).collect::<Vec<_>>()
+    );
+    assert_eq!(ids(&discography.singles), ["2000", "2001"]);
+    assert_eq!(discography.compilations.len(), 100);
+    // two pages of albums, one of singles, and the repeated compilations page once
+    assert_eq!(server.hits("/artist/albums"), 5);
+}
+
+#[tokio::test]
+async fn pages_through_a_playlist() {
+    const PLAYLIST: &str = "36ea71a8-445e-41a4-82ab-6628c581535d";
+
+    let server = MockServer::start().await.unwrap();
+    server.data(
+        &format!("/playlist?id={PLAYLIST}"),
+        serde_json::json!({
+            "uuid": PLAYLIST,
+            "title": "mock playlist",
+            "description": null,
+            "creator": { "id": 0 },
+            "squareImage": null,
+            "image": "00000000-0000-0000-0000-000000000000",
+        }),
+    );
+
+    // the tracks come wrapped in items, the same way albums list theirs
+    let mut first = sample_album(1, &(100..199).collect::<Vec<_>>())["items"].take();
+    first.as_array_mut().unwrap().insert(
+        50,
+        serde_json::json!({ "type": "video", "item": { "id": 5 } }),
+    );
+    let second = sample_album(2, &(199..230).collect::<Vec<_>>())["items"].take();
+
+    server.data(
+        &format!("/playlist/items?id={PLAYLIST}&offset=0"),
+        serde_json::json!({
+            "limit": 100, "offset": 0, "totalNumberOfItems": 131, "items": first,
+        }),
+    );
+    server.data(
+        &format!("/playlist/items?id={PLAYLIST}&offset=100"),
+        serde_json::json!({
+            "limit": 100, "offset": 100, "totalNumberOfItems": 131, "items": second,
+        }),
+    );
+
+    let playlist = server
+        .endpoint()
+        .api()
+        .playlist(PLAYLIST.parse::<uuid::Uuid>().unwrap())
+        .await
+        .unwrap();
+
+    // the video is skipped, every track on both pages is kept in order
+    let ids = playlist
+        .tracks
+        .iter()
+        .map(|t| t.id.to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(ids, (100..230).map(|id| id.to_string()).collect::<Vec<_>>());
+    assert!(playlist.cover.is_some());
+    assert_eq!(server.hits("/playlist/items"), 2);
+}
+
+#[tokio::test]
+async fn searches_every_type_at_once() {
+    let server = MockServer::start().await.unwrap();
+    let page = |items: serde_json::Value, total: u32| serde_json::json!({ "limit": 5, "offset": 0, "totalNumberOfItems": total, "items": items });
+    let album = sample_album(1, &[10, 11]);
+    let tracks = album["items"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|i| i["item"].clone())
+        .collect::<serde_json::Value>();
+
+    // tracks come back as a bare page, everything else nested under its type
+    server.data("/search?s=mock&limit=5&offset=0", page(tracks, 12));
+    server.data(
+        "/search?al=mock&limit=5&offset=0",
+        serde_json::json!({ "albums": page(serde_json::json!([album]), 1) }),
+    );
+    server.data(
+        "/search?a=mock&limit=5&offset=0",
+        serde_json::json!({
+            "artists": page(serde_json::json!([{ "id": 1, "name": "mock artist" }]), 1),
+        }),
+    );
+    server.data(
+        "/search?p=mock&limit=5&offset=0",
+        serde_json::json!({ "playlists": page(serde_json::json!([]), 0) }),
+    );
+
+    let results = server
+        .endpoint()
+        .api()
+        .search_all(SearchQuery::new("mock").kind(SearchType::Albums).limit(5))
+        .await
+        .unwrap();
+
+    // the type filter is ignored, the limit isn't
+    assert_eq!(results.tracks.items.len(), 2);
+    assert_eq!(results.tracks.total_number_of_items, Some(12));
+    assert_eq!(results.albums.items[0].title, "mock album");
+    assert_eq!(results.artists.items[0].name, "mock artist");
+    assert!(results.playlists.items.is_empty());
+    assert_eq!(server.hits("/search"), 4);
+}
+
+#[tokio::test]
+async fn artist_picture_skips_the_discography() {
+    let server = MockServer::start().await.unwrap();
+    server.data(
+        "/artist?id=1",
+        serde_json::json!({ "id": 1, "name": "mock artist", "picture": null }),
+    );
+
+    let picture = server
+        .endpoint()
+        .api()
+        .artist_picture(1.into(), Default::default())
+        .await
+        .unwrap();
+
+    assert!(picture.is_none());
+    assert_eq!(server.hits("/artist"), 1);
+    assert_eq!(server.hits("/artist/albums"), 0);
+}
+
+#[tokio::test]
+async fn classifies_status_errors() {
+    let server = MockServer::start().await.unwrap();
+    server.respond("/album?id=2", 503, "text/plain", "down for maintenance");
+
+    let monochrome = server.endpoint().api();
+
+    // nothing is routed for this one, so the mock answers 404
+    let missing = monochrome.album(1).await.unwrap_err();
+    match &missing {
+        MonochromeError::Status {
+            status,
+            endpoint,
+            kind,
+            ..
+        } => {
+            assert_eq!(status.as_u16(), 404);
+            assert_eq!(*kind, RequestKind::Api);
+            assert_eq!(endpoint, server.url().join("album").unwrap().as_str());
+        }
+        e => panic!("expected a status error, got {e}"),
+    }
+    assert!(!missing.is_retryable());
+
+    let down = monochrome.album(2).await.unwrap_err();
+    assert_eq!(down.status().map(|s| s.as_u16()), Some(503));
+    assert!(down.is_retryable());
+
+    for (status, transient) in [
+        (503, true),
+        (429, true),
+        (408, true),
+        (404, false),
+        (403, false),
+    ] {
+        let status = reqwest::StatusCode::from_u16(status).unwrap();
+        assert_eq!(monochrome::is_transient(status), transient, "{status}");
+    }
+}
+
+#[tokio::test]
+async fn waits_out_a_rate_limit() {
+    let server = MockServer::start().await.unwrap();
+    server.data("/album?id=1", sample_album(1, &[10]));
+    server.inject(
+        "/album?id=1",
+        Fault::RateLimited {
+            retry_after_secs: 1,
+        },
+    );
+
+    let album = server.endpoint().api().album(1).await.unwrap();
+
+    assert_eq!(album.tracks.len(), 1);
+    assert_eq!(server.hits("/album"), 2);
+}
+
+#[tokio::test]
+async fn keeps_only_the_start_of_an_error_body() {
+    let server = MockServer::start().await.unwrap();
+    server.respond("/album?id=1", 404, "text/plain", "x".repeat(1 << 20));
+
+    let err = server.endpoint().api().album(1).await.unwrap_err();
+    match err {
+        MonochromeError::Status { body, .. } => assert_eq!(body.len(), 64 << 10),
+        e => panic!("expected a status error, got {e}"),
+    }
+}
+
+#[tokio::test]
+async fn rescans_after_a_hung_request() {
+    let server = MockServer::start().await.unwrap();
+    server.data("/album?id=1", sample_album(1, &[10]));
+    server.inject("/album?id=1", Fault::Hang);
+
+    let endpoint = server.endpoint().with_timeouts(TimeoutPolicy {
+        metadata: Timeouts {
+            first_byte: Duration::from_millis(200),
+            read_idle: Duration::from_millis(200),
+        },
+        ..Default::default()
+    });
+
+    let album = endpoint.api().album(1).await.unwrap();
+
+    assert_eq!(album.tracks.len(), 1);
+    assert_eq!(server.hits("/uptime"), 1);
+}
+
+#[tokio::test]
+async fn resumes_a_truncated_file() {
+    let server = MockServer::start().await.unwrap();
+    let file = (0..4096).map(|i| i as u8).collect::<Vec<_>>();
+    server.bts_track(1, file.clone());
+    server.inject("/files/1.flac", Fault::Truncate(1000));
+
+    let monochrome = server.endpoint().api().with_segment_retry(quick_retries());
+    let (bytes, _, resumes) = download(&monochrome, 1).await;
+
+    assert_eq!(bytes, file);
+    assert_eq!(resumes, 1);
+}
+
+#[tokio::test]
+async fn realigns_a_resume_that_starts_at_the_wrong_byte() {
+    let server = MockServer::start().await.unwrap();
+    let file = (0..4096).map(|i| i as u8).collect::<Vec<_>>();
+    server.bts_track(1, file.clone());
+    // the first resume would leave a hole and is retried, the second starts early and is trimmed
+    server.inject("/files/1.flac", Fault::Truncate(1000));
+    server.inject("/files/1.flac", Fault::RangeFrom(3000));
+    server.inject("/files/1.flac", Fault::RangeFrom(500));
+
+    let monochrome = server.endpoint().api().with_segment_retry(quick_retries());
+    let (bytes, _, resumes) = download(&monochrome, 1).await;
+
+    assert_eq!(bytes, file);
+    assert_eq!(resumes, 2);
+}
+
+#[tokio::test]
+async fn decrypts_an_encrypted_file_across_resumes() {
+    let server = MockServer::start().await.unwrap();
+    let file = (0..4096).map(|i| (i * 7) as u8).collect::<Vec<_>>();
+    server.encrypted_bts_track(1, file.clone());
+    // cut mid block, so the resumed request has to pick the keystream up where it left off
+    server.inject("/files/1.flac", Fault::Truncate(1000));
+
+    let monochrome = server.endpoint().api().with_segment_retry(quick_retries());
+    let (bytes, _, resumes) = download(&monochrome, 1).await;
+
+    assert_eq!(bytes, file);
+    assert_eq!(resumes, 1);
+}
+
+#[tokio::test]
+async fn rejects_unknown_encryption() {
+    let server = MockServer::start().await.unwrap();
+    let manifest = serde_json::json!({
+        "mimeType": "audio/flac",
+        "encryptionType": "NEW_AES",
+        "urls": [server.url().join("files/1.flac").unwrap()],
+    });
+    server.manifest(
+        1,
+        "application/vnd.tidal.bts",
+        manifest.to_string().as_bytes(),
+    );
+
+    let monochrome = server.endpoint().api();
+    let manifest = monochrome
+        .track_manifest(1, AudioQuality::Lossless)
+        .await
+        .unwrap();
+
+    match monochrome
+        .download_track(&manifest, Arc::new(Semaphore::new(4)))
+        .await
+    {
+        Err(MonochromeError::UnsupportedEncryption(kind)) => assert_eq!(kind, "NEW_AES"),
+        Err(e) => panic!("expected unsupported encryption, got {e}"),
+        Ok(_) => panic!("expected unsupported encryption, got a stream"),
+    }
+}
+
+#[tokio::test]
+async fn refuses_a_preview_without_falling_back() {
+    let server = MockServer::start().await.unwrap();
+    server.bts_track(3, vec![3; 1024]);
+    server.data(
+        "/track?id=3&quality=HI_RES_LOSSLESS",
+        serde_json::json!({
+            "trackId": 3,
+            "assetPresentation": "PREVIEW",
+            "manifestMimeType": "application/vnd.tidal.bts",
+            "manifest": "",
+            "audioQuality": "HI_RES_LOSSLESS",
+        }),
+    );
+
+    let monochrome = server.endpoint().api();
+    let err = monochrome
+        .track_manifest(3, AudioQuality::HiResLossless)
+        .await
+        .unwrap_err();
+    assert!(
+        matches!(
+            err,
+            MonochromeError::Manifest(MonochromeManifestError::Preview)
+        ),
+        "{err}"
+    );
+    assert!(!err.is_retryable());
+
+    // falling back steps down to the tier that is served in full
+    let manifest = monochrome
+        .track_manifest_with_fallback(3, AudioQuality::HiResLossless)
+        .await
+        .unwrap();
+    assert_eq!(manifest.asset_presentation, "FULL");
+    assert_eq!(manifest.audio_quality, Some(AudioQuality::Lossless));
+}
+
+#[tokio::test]
+async fn retries_a_failed_segment() {
+    let server = MockServer::start().await.unwrap();
+    let segments = (1..=3u8)
+        .map(|i| Bytes::from(vec![i; 256]))
+        .collect::<Vec<_>>();
+    server.dash_track(2, vec![0u8; 64], segments.clone());
+    server.inject("/segments/2/2.mp4", Fault::Status(500));
+
+    let monochrome = server.endpoint().api().with_segment_retry(quick_retries());
+    let (bytes, retries, _) = download(&monochrome, 2).await;
+
+    let expected = [vec![0u8; 64], segments.concat()].concat();
+    assert_eq!(bytes, expected);
+    assert_eq!(retries, 1);
+}
+
+#[tokio::test]
+async fn spreads_segments_without_asking_every_mirror() {
+    let mut mirrors = Vec::new();
+    for _ in 0..5 {
+        mirrors.push(MockServer::start().await.unwrap());
+    }
+
+    let segments = (1..=4u8)
+        .map(|i| Bytes::from(vec![i; 256]))
+        .collect::<Vec<_>>();
+    for mirror in &mirrors {
+        mirror.dash_track(2, vec![0u8; 64], segments.clone());
+    }
+
+    let urls = mirrors.iter().map(MockServer::url).collect::<Vec<_>>();
+    let monochrome = mirrors[0]
+        .endpoint()
+        .with_mirrors(MirrorList {
+            api: urls.clone(),
+            streaming: urls,
+        })
+        .api()
+        .with_segment_fanout(2);
+    let (bytes, _, _) = download(&monochrome, 2).await;
+
+    let expected = [vec![0u8; 64], segments.concat()].concat();
+    assert_eq!(bytes, expected);
+
+    // the original manifest, then one more, plus at most the original mirror again on the way
+    let manifests: usize = mirrors.iter().map(|m| m.hits("/track")).sum();
+    assert!(manifests <= 3, "asked {manifests} mirrors for the manifest");
+
+    let serving = mirrors
+        .iter()
+        .filter(|m| (1..=4).any(|i| m.hits(&format!("/segments/2/{i}.mp4")) > 0))
+        .count();
+    assert_eq!(serving, 2);
+}
+
+#[tokio::test]
+async fn prefetches_a_bounded_window_and_stops_when_dropped() {
+    let server = MockServer::start().await.unwrap();
+    let segments = (1..=8u8)
+        .map(|i| Bytes::from(vec![i; 256]))
+        .collect::<Vec<_>>();
+    server.dash_track(4, vec![0u8; 64], segments);
+    for i in 1..=8 {
+        server.inject(&format!("/segments/4/{i}.mp4"), Fault::Hang);
+    }
+
+    let monochrome = server.endpoint().api().with_prefetch_window(3);
+    let manifest = monochrome
+        .track_manifest(4, AudioQuality::Lossless)
+        .await
+        .unwrap();
+    let mut stream = monochrome
+        .download_track(&manifest, Arc::new(Semaphore::new(8)))
+        .await
+        .unwrap();
+
+    assert_eq!(stream.try_next().await.unwrap().unwrap().len(), 64);
+
+    // every segment hangs, so the window fills up and stays full
+    let next = tokio::time::timeout(Duration::from_millis(300), stream.try_next()).await;
+    assert!(next.is_err());
+    assert_eq!(server.in_flight(), 3);
+    assert_eq!(server.hits("/segments/4/4.mp4"), 0);
+
+    drop(stream);
+
+    let start = Instant::now();
+    while server.in_flight() > 0 {
+        assert!(
+            start.elapsed() < Duration::from_secs(5),
+            "segment requests outlived the stream"
+        );
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+}
+
+#[tokio::test]
+async fn scan_finds_mirrors_through_uptime() {
+    let server = MockServer::start().await.unwrap();
+    let other = MockServer::start().await.unwrap();
+    server.set_uptime(MirrorList {
+        api: vec![server.url(), other.url()],
+        streaming: vec![other.url()],
+    });
+
+    let endpoint = server.endpoint();
+    endpoint.scan().await.unwrap();
+
+    let mut api = endpoint.api_mirrors().await;
+    api.sort();
+    let mut expected = vec![server.url(), other.url()];
+    expected.sort();
+
+    assert_eq!(api, expected);
+    assert_eq!(endpoint.streaming_mirrors().await, vec![other.url()]);
+}
+
+#[tokio::test]
+async fn scan_records_capabilities() {
+    let server = MockServer::start().await.unwrap();
+    let lossless = MockServer::start().await.unwrap();
+    lossless.probe_quality(AudioQuality::Lossless);
+    server.set_uptime(MirrorList {
+        api: vec![server.url()],
+        streaming: vec![lossless.url(), server.url()],
+    });
+
+    let endpoint = server.endpoint();
+    endpoint.scan().await.unwrap();
+    let capabilities = endpoint.capabilities().await;
+
+    let hires = &capabilities[&server.url()];
+    assert_eq!(hires.max_quality, Some(AudioQuality::HiResLossless));
+    assert!(!hires.previews);
+    assert_eq!(hires.manifest, Some(ManifestKind::Bts));
+    assert!(hires.throughput.is_some());
+    // and goes into how often the mirror gets picked
+    assert_eq!(
+        endpoint.health()[&server.url()].throughput,
+        hires.throughput
+    );
+
+    let limited = &capabilities[&lossless.url()];
+    assert_eq!(limited.max_quality, Some(AudioQuality::Lossless));
+    assert!(limited.previews);
+    assert!(limited.serves(AudioQuality::High));
+    assert!(!limited.serves(AudioQuality::HiResLossless));
+
+    // the better tier goes first
+    assert_eq!(
+        endpoint.streaming_mirrors().await,
+        vec![server.url(), lossless.url()]
+    );
+}
+
+#[tokio::test]
+async fn clamps_an_absurd_retry_after() {
+    let server = MockServer::start().await.unwrap();
+    server.data("/album?id=1", sample_album(1, &[10]));
+    server.inject(
+        "/album?id=1",
+        Fault::RateLimited {
+            retry_after_secs: u64::MAX,
+        },
+    );
+
+    let endpoint = server.endpoint().with_rate_limits(RateLimitPolicy {
+        max_backoff: Duration::from_millis(50),
+        ..Default::default()
+    });
+
+    // the retry waits out the clamped backoff instead of the mirror's hundreds of billions of years
+    let album = tokio::time::timeout(Duration::from_secs(5), endpoint.api().album(1))
+        .await
+        .expect("the backoff should be clamped")
+        .unwrap();
+    assert_eq!(album.tracks.len(), 1);
+}
+
+#[tokio::test]
+async fn probes_wait_for_request_budget() {
+    let server = MockServer::start().await.unwrap();
+    let lossless = MockServer::start().await.unwrap();
+    lossless.probe_quality(AudioQuality::Lossless);
+    server.set_uptime(MirrorList {
+        api: vec![server.url()],
+        streaming: vec![lossless.url()],
+    });
+
+    // the probe asks for hi-res, gets a preview, then asks for lossless, and the second ask has
+    // to wait a quarter second for a token
+    let endpoint = server.endpoint().with_rate_limits(RateLimitPolicy {
+        default: RateLimit::new(1, 4.0).unwrap(),
+        ..Default::default()
+    });
+
+    let start = Instant::now();
+    endpoint.scan().await.unwrap();
+
+    assert!(start.elapsed() >= Duration::from_millis(200));
+    assert_eq!(
+        endpoint.capabilities().await[&lossless.url()].max_quality,
+        Some(AudioQuality::Lossless)
+    );
+}
+
+#[tokio::test]
+async fn probes_wait_out_a_429_instead_of_skipping_the_tier() {
+    let server = MockServer::start().await.unwrap();
+    // the track every scan probes with
+    server.inject(
+        "/track?id=109485855&quality=HI_RES_LOSSLESS",
+        Fault::RateLimited {
+            retry_after_secs: 0,
+        },
+    );
+
+    let endpoint = server.endpoint();
+    endpoint.scan().await.unwrap();
+
+    assert_eq!(
+        endpoint.capabilities().await[&server.url()].max_quality,
+        Some(AudioQuality::HiResLossless)
+    );
+}
+
+#[tokio::test]
+async fn routes_tracks_to_mirrors_that_serve_the_tier() {
+    let server = MockServer::start().await.unwrap();
+    let lossless = MockServer::start().await.unwrap();
+    lossless.probe_quality(AudioQuality::Lossless);
+    server.set_uptime(MirrorList {
+        api: vec![server.url()],
+        streaming: vec![lossless.url(), server.url()],
+    });
+
+    for mirror in [&server, &lossless] {
+        mirror.bts_track(1, vec![1; 1024]);
+    }
+
+    let endpoint = server.endpoint();
+    endpoint.scan().await.unwrap();
+    let monochrome = endpoint.api();
+
+    let before = (server.hits("/track"), lossless.hits("/track"));
+    for _ in 0..10 {
+        monochrome
+            .track_manifest(1, AudioQuality::HiResLossless)
+            .await
+            .unwrap();
+    }
+
+    assert_eq!(server.hits("/track") - before.0, 10);
+    assert_eq!(lossless.hits("/track"), before.1);
+}
@@ -0,0 +1,197 @@
+use monochrome::{
+    MonochromeManifestError,
+    mpd::{self, ByteRange, SegmentPlan},
+};
+
+fn fixture(name: &str) -> String {
+    let path = format!("{}/tests/fixtures/mpd/{name}", env!("CARGO_MANIFEST_DIR"));
+    std::fs::read_to_string(&path).unwrap_or_else(|e| panic!("failed to read {path}: {e}"))
+}
+
+fn plan(name: &str) -> SegmentPlan {
+    mpd::parse(&fixture(name), None).unwrap()
+}
+
+fn urls(plan: &SegmentPlan) -> Vec<&str> {
+    plan.segments.iter().map(|s| s.url.as_str()).collect()
+}
+
+#[test]
+fn number_timeline() {
+    let plan = plan("tidal_number_timeline.mpd");
+
+    assert_eq!(plan.representation_id.as_deref(), Some("FLAC,44100,16"));
+    assert_eq!(plan.codecs.as_deref(), Some("flac"));
+    assert_eq!(
+        plan.initialization.as_ref().unwrap().url.as_str(),
+        "https://sp-ad-cf.audio.tidal.com/mediatracks/abc/0.mp4?token=x"
+    );
+
+    // r="50" is 51 segments, plus the trailing one
+    assert_eq!(plan.segments.len(), 52);
+    assert_eq!(
+        plan.segments[0].url.as_str(),
+        "https://sp-ad-cf.audio.tidal.com/mediatracks/abc/1.mp4?token=x"
+    );
+    assert_eq!(
+        plan.segments[51].url.as_str(),
+        "https://sp-ad-cf.audio.tidal.com/mediatracks/abc/52.mp4?token=x"
+    );
+}
+
+#[test]
+fn time_template_picks_highest_bandwidth() {
+    let plan = plan("time_multi_representation.mpd");
+
+    assert_eq!(plan.representation_id.as_deref(), Some("high"));
+    assert_eq!(plan.bandwidth, Some(320000));
+    assert_eq!(plan.codecs.as_deref(), Some("mp4a.40.2"));
+    assert_eq!(
+        plan.initialization.as_ref().unwrap().url.as_str(),
+        "https://cdn.example.com/media/track/high/init.mp4"
+    );
+    assert_eq!(
+        urls(&plan),
+        [
+            "https://cdn.example.com/media/track/high/320000/0.m4s",
+            "https://cdn.example.com/media/track/high/320000/4000.m4s",
+            "https://cdn.example.com/media/track/high/320000/8000.m4s",
+        ]
+    );
+}
+
+#[test]
+fn timeline_starts_at_zero_whatever_the_presentation_offset() {
+    let manifest = fixture("time_multi_representation.mpd")
+        .replace(
+            r#"timescale="1000""#,
+            r#"timescale="1000" presentationTimeOffset="2000""#,
+        )
+        .replace(r#"<S t="0" d="4000" r="1"/>"#, r#"<S d="4000" r="1"/>"#);
+    let plan = mpd::parse(&manifest, None).unwrap();
+    assert_eq!(
+        urls(&plan),
+        [
+            "https://cdn.example.com/media/track/high/320000/0.m4s",
+            "https://cdn.example.com/media/track/high/320000/4000.m4s",
+            "https://cdn.example.com/media/track/high/320000/8000.m4s",
+        ]
+    );
+
+    // an explicit start on the first S still wins
+    let manifest = manifest.replace(r#"<S d="4000" r="1"/>"#, r#"<S t="2000" d="4000" r="1"/>"#);
+    let plan = mpd::parse(&manifest, None).unwrap();
+    assert_eq!(
+        urls(&plan),
+        [
+            "https://cdn.example.com/media/track/high/320000/2000.m4s",
+            "https://cdn.example.com/media/track/high/320000/6000.m4s",
+            "https://cdn.example.com/media/track/high/320000/10000.m4s",
+        ]
+    );
+}
+
+#[test]
+fn negative_repeat() {
+    let plan = plan("negative_repeat.mpd");
+
+    // 0..60 in steps of 20, then 60..100 (the period end) in steps of 15
+    assert_eq!(
+        urls(&plan),
+        [
+            "https://cdn.example.com/00000.m4s",
+            "https://cdn.example.com/00001.m4s",
+            "https://cdn.example.com/00002.m4s",
+            "https://cdn.example.com/00003.m4s",
+            "https://cdn.example.com/00004.m4s",
+            "https://cdn.example.com/00005.m4s",
+        ]
+    );
+}
+
+#[test]
+fn negative_repeat_without_end() {
+    let err = mpd::parse(&fixture("negative_repeat_unbounded.mpd"), None).unwrap_err();
+    assert!(matches!(
+        err,
+        MonochromeManifestError::NegativeRepeatUnsupported
+    ));
+}
+
+#[test]
+fn duration_template() {
+    let plan = plan("duration_template.mpd");
+
+    assert_eq!(
+        plan.initialization.as_ref().unwrap().url.as_str(),
+        "https://cdn.example.com/dur/init.mp4"
+    );
+    // 60.5s in 10s segments
+    assert_eq!(plan.segments.len(), 7);
+    assert_eq!(
+        plan.segments[6].url.as_str(),
+        "https://cdn.example.com/dur/seg-7-$.m4s"
+    );
+}
+
+#[test]
+fn segment_list() {
+    let plan = plan("segment_list.mpd");
+
+    let init = plan.initialization.as_ref().unwrap();
+    assert_eq!(init.url.as_str(), "https://cdn.example.com/list/track.mp4");
+    assert_eq!(init.range, Some(ByteRange { start: 0, end: 861 }));
+
+    assert_eq!(plan.segments.len(), 2);
+    assert_eq!(
+        plan.segments[0].url.as_str(),
+        "https://cdn.example.com/list/track.mp4"
+    );
+    assert_eq!(
+        plan.segments[0].range,
+        Some(ByteRange {
+            start: 862,
+            end: 50000
+        })
+    );
+    assert_eq!(
+        plan.segments[1].url.as_str(),
+        "https://other.example.com/part2.m4s"
+    );
+    assert_eq!(plan.segments[1].range, None);
+}
+
+#[test]
+fn segment_base() {
+    let plan = plan("segment_base.mpd");
+
+    assert_eq!(plan.representation_id.as_deref(), Some("hi"));
+    assert!(plan.initialization.is_none());
+    assert_eq!(urls(&plan), ["https://cdn.example.com/base/hi.mp4"]);
+}
+
+#[test]
+fn relative_urls_need_a_base() {
+    let manifest = fixture("time_multi_representation.mpd")
+        .replace("<BaseURL>https://cdn.example.com/media/</BaseURL>", "");
+    assert!(matches!(
+        mpd::parse(&manifest, None).unwrap_err(),
+        MonochromeManifestError::BadBaseUrl
+    ));
+
+    let base = "https://fallback.example.com/".parse().unwrap();
+    let plan = mpd::parse(&manifest, Some(&base)).unwrap();
+    assert_eq!(
+        plan.segments[0].url.as_str(),
+        "https://fallback.example.com/track/high/320000/0.m4s"
+    );
+}
+
+#[test]
+fn durations() {
+    assert_eq!(mpd::parse_duration("PT3M25.347S"), Some(205.347));
+    assert_eq!(mpd::parse_duration("PT1H"), Some(3600.0));
+    assert_eq!(mpd::parse_duration("P1DT1S"), Some(86401.0));
+    assert_eq!(mpd::parse_duration("PT5"), None);
+    assert_eq!(mpd::parse_duration("5S"), None);
+}
@@ -0,0 +1,54 @@
+use monochrome::page::Page;
+use serde_json::{Value, json};
+
+fn page(value: Value) -> Page<u32> {
+    serde_json::from_value(value).unwrap()
+}
+
+#[test]
+fn advances_by_the_items_returned() {
+    let first = page(json!({
+        "limit": 3, "offset": 0, "totalNumberOfItems": 5, "items": [1, 2, 3],
+    }));
+    assert_eq!(first.next_offset(0), Some(3));
+
+    let last = page(json!({
+        "limit": 3, "offset": 3, "totalNumberOfItems": 5, "items": [4, 5],
+    }));
+    assert_eq!(last.next_offset(3), None);
+
+    // a mirror clamping the limit still gets paged through
+    let clamped = page(json!({
+        "limit": 100, "offset": 0, "totalNumberOfItems": 5, "items": [1, 2],
+    }));
+    assert_eq!(clamped.next_offset(0), Some(2));
+}
+
+#[test]
+fn stops_when_the_offset_is_ignored() {
+    // the mirror handed back the first page again instead of the one at 3
+    let repeated = page(json!({
+        "limit": 3, "offset": 0, "totalNumberOfItems": 9, "items": [1, 2, 3],
+    }));
+    assert_eq!(repeated.next_offset(3), None);
+
+    let empty = page(json!({ "limit": 3, "totalNumberOfItems": 9, "items": [] }));
+    assert_eq!(empty.next_offset(3), None);
+
+    // without an offset in the answer, the requested one is trusted
+    let missing = page(json!({ "limit": 3, "totalNumberOfItems": 9, "items": [4, 5, 6] }));
+    assert_eq!(missing.next_offset(3), Some(6));
+}
+
+#[test]
+fn keeps_going_without_a_total() {
+    let full = page(json!({ "limit": 3, "offset": 0, "items": [1, 2, 3] }));
+    assert_eq!(full.total_number_of_items, None);
+    assert_eq!(full.next_offset(0), Some(3));
+
+    let short = page(json!({ "limit": 3, "offset": 3, "items": [4] }));
+    assert_eq!(short.next_offset(3), None);
+
+    let unlimited = page(json!({ "items": [1, 2] }));
+    assert_eq!(unlimited.next_offset(0), Some(2));
+}
@@ -0,0 +1,53 @@
+use std::time::Duration;
+
+use monochrome::ratelimit::{InvalidRateLimit, RateLimit, RateLimitPolicy};
+use serde_json::json;
+
+#[test]
+fn accepts_a_valid_policy() {
+    let policy: RateLimitPolicy = serde_json::from_value(json!({
+        "default": { "burst": 2, "per_second": 0.5 },
+        "mirrors": { "triton.squid.wtf": { "burst": 16, "per_second": 10.0 } },
+        "max_backoff_secs": 60,
+    }))
+    .unwrap();
+
+    assert_eq!(policy.default.burst, 2);
+    assert_eq!(policy.mirrors["triton.squid.wtf"].per_second, 10.0);
+    assert_eq!(policy.max_backoff, Duration::from_secs(60));
+}
+
+#[test]
+fn rejects_a_zero_burst() {
+    let err =
+        serde_json::from_value::<RateLimit>(json!({ "burst": 0, "per_second": 4.0 })).unwrap_err();
+    assert!(err.to_string().contains("burst"), "{err}");
+
+    // overrides are checked too, not just the default
+    let policy = serde_json::from_value::<RateLimitPolicy>(json!({
+        "mirrors": { "triton.squid.wtf": { "burst": 0, "per_second": 4.0 } },
+    }));
+    assert!(policy.is_err());
+
+    assert_eq!(
+        RateLimit::new(0, 4.0).unwrap_err(),
+        InvalidRateLimit::ZeroBurst
+    );
+}
+
+#[test]
+fn rejects_a_rate_that_never_refills() {
+    for per_second in [0.0, -1.0] {
+        let err = serde_json::from_value::<RateLimit>(json!({
+            "burst": 8,
+            "per_second": per_second,
+        }))
+        .unwrap_err();
+        assert!(err.to_string().contains("per_second"), "{err}");
+    }
+
+    assert!(matches!(
+        RateLimit::new(8, f64::NAN),
+        Err(InvalidRateLimit::Rate(_))
+    ));
+}
@@ -1,11 +1,10 @@
-use crate::{bot::progress::ProgressTaskMessage, config::Config};
-use monochrome::Monochrome;
+use crate::{bot::progress::ProgressTaskMessage, config::Config, source::MusicSource};
 use std::sync::Arc;
 use tokio::sync::{Semaphore, mpsc};
 
 pub struct Data {
     pub config: Arc<Config>,
-    pub client: Monochrome,
+    pub source: Arc<dyn MusicSource>,
     pub track_semaphore: Arc<Semaphore>,
     pub chunk_semaphore: Arc<Semaphore>,
     pub progress_tx: mpsc::UnboundedSender<ProgressTaskMessage>,
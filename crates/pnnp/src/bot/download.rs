@@ -1,12 +1,49 @@
 use super::{Context, Error};
-use crate::bot::search::{self, SearchKind};
+use crate::bot::{
+    queue,
+    search::{self, SearchKind},
+};
+use monochrome::id::TidalRef;
 
-/// searches for and downloads singles and albums
+/// searches for and downloads singles, albums, artists and playlists, or downloads a tidal link
 #[poise::command(slash_command, prefix_command)]
 pub async fn download(
     ctx: Context<'_>,
-    #[description = "album or single name to search for"] query: String,
-    #[description = "what to search for"] kind: SearchKind,
+    #[description = "album or single name to search for, or a tidal link"] query: String,
+    #[description = "what to search for (everything if omitted)"] kind: Option<SearchKind>,
 ) -> Result<(), Error> {
-    search::search_command(ctx, &query, kind).await
+    match query.parse::<TidalRef>() {
+        Ok(tidal_ref) => link_command(ctx, tidal_ref).await,
+        Err(_) => search::search_command(ctx, &query, kind).await,
+    }
+}
+
+async fn link_command(ctx: Context<'_>, tidal_ref: TidalRef) -> Result<(), Error> {
+    ctx.defer().await?;
+    let data = ctx.data();
+
+    tracing::info!(?tidal_ref, "downloading from link");
+
+    let queued = match queue::resolve(data.source.as_ref(), tidal_ref).await {
+        Ok(queued) => queued,
+        Err(e) => {
+            tracing::error!(error = %e, "failed to resolve link");
+            ctx.say(format!("failed to fetch link: {e}")).await?;
+            return Ok(());
+        }
+    };
+
+    ctx.say(format!(
+        "your download (**{}**) will start soon! check <#{}> for progress updates",
+        queued.label, data.config.bot.progress_channel
+    ))
+    .await?;
+
+    let failures = queue::download_albums(data, queued.albums).await?;
+    if !failures.is_empty() {
+        ctx.say(format!("failed to download: {}", failures.join("\n")))
+            .await?;
+    }
+
+    Ok(())
 }
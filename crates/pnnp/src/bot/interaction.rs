@@ -1,191 +1,218 @@
 use super::{Data, Error};
-use crate::{
-    bot::progress::{self, ProgressTaskMessage},
-    config::Config,
-    pipeline::Pipeline,
-    track_or_album::TrackOrAlbum,
-};
-use monochrome::{Monochrome, album::Album};
+use crate::bot::{queue, search};
+use monochrome::album::Album;
 use poise::serenity_prelude::{
-    self as serenity, ComponentInteractionDataKind, CreateInteractionResponseFollowup,
+    self as serenity, ComponentInteractionDataKind, CreateInteractionResponse,
+    CreateInteractionResponseFollowup, CreateInteractionResponseMessage,
 };
-use std::sync::Arc;
-use tokio::sync::mpsc;
+use uuid::Uuid;
 
 pub async fn handle_interaction(
     ctx: &serenity::Context,
     interaction: &serenity::Interaction,
     data: &Data,
 ) -> Result<(), Error> {
-    match interaction {
-        serenity::Interaction::Component(i) => {
-            if let Some(m) = i.message.interaction_metadata.as_deref() {
-                if !is_from(m, i.user.id) {
+    if let serenity::Interaction::Component(i) = interaction {
+        if let Some(m) = i.message.interaction_metadata.as_deref()
+            && !is_from(m, i.user.id)
+        {
+            i.create_followup(
+                &ctx.http,
+                CreateInteractionResponseFollowup::new()
+                    .content("sorry, you can't interact with this")
+                    .ephemeral(true),
+            )
+            .await?;
+            return Ok(());
+        }
+        match i.data.custom_id.as_str() {
+            "album_select" | "track_select" => {
+                tracing::info!("handling music select interaction");
+                // options are `<album id>:<track or album id>`, see `search::music_menu`
+                let Some((album_id, music_id)) = selected(i)
+                    .and_then(|s| s.split_once(':'))
+                    .and_then(|(album, music)| {
+                        Some((album.parse::<u64>().ok()?, music.parse::<u64>().ok()?))
+                    })
+                else {
+                    tracing::error!("no music id found in interaction data");
                     i.create_followup(
                         &ctx.http,
-                        CreateInteractionResponseFollowup::new()
-                            .content("sorry, you can't interact with this")
-                            .ephemeral(true),
+                        CreateInteractionResponseFollowup::new().content(
+                            "no music id found in interaction data... this shouldn't happen!",
+                        ),
                     )
                     .await?;
                     return Ok(());
-                }
-            }
-            match i.data.custom_id.as_str() {
-                "album_select" | "track_select" => {
-                    tracing::info!("handling music select interaction");
-                    let ComponentInteractionDataKind::StringSelect { values } = &i.data.kind else {
-                        tracing::error!("unexpected interaction data kind");
-                        return Ok(());
-                    };
+                };
+
+                tracing::info!(%album_id, %music_id, "selected music id");
+
+                i.defer(&ctx.http).await?;
+
+                tracing::info!(kind = %i.data.custom_id, "deferring interaction response");
 
-                    let Some(music_id) = values
-                        .first()
-                        .and_then(|s| s.split(":").next())
-                        .map(|s| s.parse::<u64>().ok())
-                        .flatten()
-                    else {
-                        tracing::error!("no music id found in interaction data");
-                        // i.create_response(&ctx.http, CreateInteractionResponse::Message(CreateInteractionResponseMessage::new().content("no album id found in interaction data... this shouldn't happen!"))).await?;
-                        i.create_followup(
-                            &ctx.http,
-                            CreateInteractionResponseFollowup::new().content(
-                                "no music id found in interaction data... this shouldn't happen!",
-                            ),
+                // a track goes through its album, narrowed down to just that track
+                let queued = if i.data.custom_id == "track_select" {
+                    queue::resolve_track(data.source.as_ref(), music_id.into()).await
+                } else {
+                    queue::resolve_album(data.source.as_ref(), album_id.into()).await
+                };
+
+                let queued = match queued {
+                    Ok(queued) => queued,
+                    Err(e) => {
+                        tracing::error!(error = %e, "failed to fetch music for selected id");
+                        followup(
+                            ctx,
+                            i,
+                            format!("failed to fetch music for selected id: {e}"),
                         )
                         .await?;
                         return Ok(());
-                    };
-
-                    tracing::info!(%music_id, "selected music id");
+                    }
+                };
 
-                    i.defer(&ctx.http).await?;
+                start_downloads(ctx, i, data, &queued.label, queued.albums).await?;
+            }
 
-                    tracing::info!(kind = %i.data.custom_id, "deferring interaction response");
+            search::ARTIST_SELECT => {
+                let Some(artist_id) = selected(i).and_then(|s| s.parse::<u64>().ok()) else {
+                    tracing::error!("no artist id found in interaction data");
+                    return Ok(());
+                };
 
-                    let album = data.client.album(music_id).await;
+                tracing::info!(%artist_id, "selected artist id");
+                i.defer(&ctx.http).await?;
 
-                    let album = match album {
-                        Ok(music) => music,
+                let queued =
+                    match queue::resolve_artist(data.source.as_ref(), artist_id.into()).await {
+                        Ok(queued) => queued,
                         Err(e) => {
-                            tracing::error!(error = %e, "failed to fetch music for selected id");
-                            // i.edit_response(
-                            //     &ctx.http,
-                            //     EditInteractionResponse::new()
-                            //         .content("failed to fetch music for selected id"),
-                            // )
-                            // .await?;
-                            i.create_followup(
-                                &ctx.http,
-                                CreateInteractionResponseFollowup::new()
-                                    .content(format!("failed to fetch music for selected id: {e}")),
-                            )
-                            .await?;
+                            tracing::error!(error = %e, "failed to fetch artist discography");
+                            followup(ctx, i, format!("failed to fetch artist: {e}")).await?;
                             return Ok(());
                         }
                     };
 
-                    if i.data.custom_id == "track_select" && album.kind != "SINGLE" {
-                        tracing::error!(kind = %album.kind, "selected track is not a single");
-                        i.create_followup(
-                            &ctx.http,
-                            CreateInteractionResponseFollowup::new().content(
-                                format!("the selected track is not a single, please use /album to download the whole album ({} - {})", album.artist.name, album.title),
-                            ),
-                        )
-                        .await?;
-
-                        return Ok(());
-                    }
+                start_downloads(ctx, i, data, &queued.label, queued.albums).await?;
+            }
 
-                    let msgs = progress::done_msgs(&album);
-
-                    i
-                        .create_followup(
-                            &ctx.http,
-                            CreateInteractionResponseFollowup::new().content(
-                                format!(
-                                    "your download (**{} - {}**) will start soon! check <#{}> for progress updates",
-                                    album.artist.name, album.title,
-                                    data.config.bot.progress_channel
-                                )
-                            )
-                        )
-                        .await?;
+            search::PLAYLIST_SELECT => {
+                let Some(playlist_id) = selected(i).and_then(|s| s.parse::<Uuid>().ok()) else {
+                    tracing::error!("no playlist id found in interaction data");
+                    return Ok(());
+                };
 
-                    if let Err(e) =
-                        handle_download(&data.client, data.config.clone(), album, data).await
-                    {
-                        tracing::error!(error = %e, "failed to download album");
+                tracing::info!(%playlist_id, "selected playlist id");
+                i.defer(&ctx.http).await?;
 
-                        for msg in msgs {
-                            data.progress_tx.send(msg)?;
+                let queued =
+                    match queue::resolve_playlist(data.source.as_ref(), playlist_id.into()).await {
+                        Ok(queued) => queued,
+                        Err(e) => {
+                            tracing::error!(error = %e, "failed to fetch playlist");
+                            followup(ctx, i, format!("failed to fetch playlist: {e}")).await?;
+                            return Ok(());
                         }
+                    };
 
-                        i.create_followup(
-                            &ctx.http,
-                            CreateInteractionResponseFollowup::new()
-                                .content(format!("failed to download: {e}")),
-                        )
-                        .await?;
-                    }
-                }
+                start_downloads(ctx, i, data, &queued.label, queued.albums).await?;
+            }
+
+            id if id.starts_with(search::PAGE_PREFIX) => {
+                let Some((kind, offset, query)) = search::parse_page_id(id) else {
+                    tracing::error!(%id, "malformed search page id");
+                    return Ok(());
+                };
+
+                tracing::info!(%query, %offset, "handling search page interaction");
+
+                let response =
+                    match search::search_message(data.source.as_ref(), query, kind, offset).await {
+                        Ok(Some(message)) => CreateInteractionResponse::UpdateMessage(
+                            CreateInteractionResponseMessage::new()
+                                .content(message.content)
+                                .components(message.components),
+                        ),
+                        Ok(None) => CreateInteractionResponse::UpdateMessage(
+                            CreateInteractionResponseMessage::new()
+                                .content(format!("no more {}s found", kind.name()))
+                                .components(Vec::new()),
+                        ),
+                        // leave the results that are there, so the button can be tried again
+                        Err(e) => {
+                            tracing::error!(error = %e, "failed to fetch search page");
+                            CreateInteractionResponse::Message(
+                                CreateInteractionResponseMessage::new()
+                                    .content(format!("failed to search: {e}"))
+                                    .ephemeral(true),
+                            )
+                        }
+                    };
 
-                _ => {}
+                i.create_response(&ctx.http, response).await?;
             }
-        }
 
-        _ => {}
+            _ => {}
+        }
     }
 
     Ok(())
 }
 
-async fn handle_download(
-    client: &Monochrome,
-    config: Arc<Config>,
-    album: Album,
+async fn start_downloads(
+    ctx: &serenity::Context,
+    i: &serenity::ComponentInteraction,
     data: &Data,
-) -> anyhow::Result<()> {
-    let (tx, mut rx) = mpsc::unbounded_channel();
-
-    // data.progress_tx
-    //     .send(ProgressTaskMessage::DiscoverAlbum(id, music.clone()))?;
-
-    data.progress_tx.send(ProgressTaskMessage::DiscoverAlbum(
-        album.id.into(),
-        album.clone(),
-    ))?;
-
-    let msgs = progress::done_msgs(&album);
-
-    let pipeline = Pipeline::new(
-        client.clone(),
-        album,
-        tx,
-        data.track_semaphore.clone(),
-        data.chunk_semaphore.clone(),
-        config,
-    );
-
-    let handle = tokio::spawn(pipeline.begin());
-
-    while let Some(update) = rx.recv().await {
-        data.progress_tx
-            .send(ProgressTaskMessage::Progress(update))?;
+    label: &str,
+    albums: Vec<Album>,
+) -> Result<(), Error> {
+    followup(
+        ctx,
+        i,
+        format!(
+            "your download (**{label}**) will start soon! check <#{}> for progress updates",
+            data.config.bot.progress_channel
+        ),
+    )
+    .await?;
+
+    let failures = queue::download_albums(data, albums).await?;
+
+    if !failures.is_empty() {
+        followup(
+            ctx,
+            i,
+            format!("failed to download: {}", failures.join("\n")),
+        )
+        .await?;
     }
 
-    for handle in handle.await? {
-        handle.await??;
-    }
+    Ok(())
+}
 
-    for msg in msgs {
-        data.progress_tx.send(msg)?;
-    }
+async fn followup(
+    ctx: &serenity::Context,
+    i: &serenity::ComponentInteraction,
+    content: String,
+) -> Result<(), Error> {
+    i.create_followup(
+        &ctx.http,
+        CreateInteractionResponseFollowup::new().content(content),
+    )
+    .await?;
 
     Ok(())
 }
 
+fn selected(i: &serenity::ComponentInteraction) -> Option<&str> {
+    match &i.data.kind {
+        ComponentInteractionDataKind::StringSelect { values } => values.first().map(String::as_str),
+        _ => None,
+    }
+}
+
 fn is_from(metadata: &serenity::MessageInteractionMetadata, user_id: serenity::UserId) -> bool {
     let original = match metadata {
         serenity::MessageInteractionMetadata::Command(c) => &c.user,
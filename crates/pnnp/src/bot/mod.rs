@@ -3,20 +3,20 @@ mod data;
 mod download;
 mod interaction;
 mod progress;
+mod queue;
 mod search;
 
 use std::sync::Arc;
 
-use crate::{bot::progress::ProgressTask, config::Config};
+use crate::{bot::progress::ProgressTask, config::Config, source::MusicSource};
 use data::Data;
-use monochrome::Monochrome;
 use poise::serenity_prelude::{self as serenity, GetMessages};
 use tokio::sync::{Semaphore, mpsc};
 
 type Error = anyhow::Error;
 type Context<'a> = poise::Context<'a, Data, Error>;
 
-pub async fn start(client: Monochrome, config: Config) -> anyhow::Result<()> {
+pub async fn start(source: Arc<dyn MusicSource>, config: Config) -> anyhow::Result<()> {
     tracing::info!("starting bot");
 
     let intents = serenity::GatewayIntents::non_privileged();
@@ -31,12 +31,8 @@ pub async fn start(client: Monochrome, config: Config) -> anyhow::Result<()> {
                 commands: vec![download::download()],
                 event_handler: |ctx, event, _, data| {
                     Box::pin(async move {
-                        match event {
-                            serenity::FullEvent::InteractionCreate { interaction } => {
-                                interaction::handle_interaction(ctx, interaction, data).await?;
-                            }
-
-                            _ => {}
+                        if let serenity::FullEvent::InteractionCreate { interaction } = event {
+                            interaction::handle_interaction(ctx, interaction, data).await?;
                         }
 
                         Ok(())
@@ -85,7 +81,7 @@ pub async fn start(client: Monochrome, config: Config) -> anyhow::Result<()> {
                             config.downloads.chunk_concurrency,
                         )),
                         config,
-                        client,
+                        source,
                         progress_tx: tx,
                     })
                 })
@@ -3,7 +3,7 @@ use chrono::{Datelike, NaiveDate};
 use monochrome::{
     album::Album,
     id::{AlbumId, TrackId},
-    track::Track,
+    manifest::StreamInfo,
 };
 use poise::serenity_prelude::{self as serenity, CreateMessage, EditMessage, Message};
 use std::{collections::HashMap, sync::Arc};
@@ -17,7 +17,6 @@ use tokio::sync::mpsc;
 use crate::{
     config::Config,
     pipeline::{ProgressState, ProgressUpdate},
-    track_or_album::TrackOrAlbum,
 };
 
 pub struct ProgressTask {
@@ -32,7 +31,6 @@ pub struct ProgressTask {
 
 pub enum ProgressTaskMessage {
     DiscoverAlbum(AlbumId, Album),
-    DiscoverTrack(TrackId, Track),
     Progress(ProgressUpdate),
     TrackDone(TrackId),
 }
@@ -51,6 +49,7 @@ struct TrackProgress {
     state: Option<ProgressState>,
     last_known_bytes: u64,
     album_id: Option<AlbumId>,
+    info: Option<StreamInfo>,
 }
 
 impl ProgressTask {
@@ -125,7 +124,7 @@ impl ProgressTask {
             return msg;
         }
 
-        let mut albums = self.albums.iter().map(|(_, a)| a).collect::<Vec<_>>();
+        let mut albums = self.albums.values().collect::<Vec<_>>();
         albums.sort_by_key(|a| a.sort);
 
         for progress in albums {
@@ -177,6 +176,19 @@ impl ProgressTask {
             )
             .ok();
 
+            // usually one format per album, but tracks can fall back on their own
+            let mut formats = Vec::new();
+            for info in tracks.iter().filter_map(|t| t.info.as_ref()) {
+                let format = info.to_string();
+                if !formats.contains(&format) {
+                    formats.push(format);
+                }
+            }
+
+            if !formats.is_empty() {
+                writeln!(msg, "format: {}", formats.join(", ")).ok();
+            }
+
             // for track in tracks {
             //     let state: Cow<'_, str> = match track.state {
             //         None => "waiting".into(),
@@ -261,6 +273,7 @@ impl ProgressTask {
                             state: None,
                             last_known_bytes: 0,
                             album_id: Some(id),
+                            info: None,
                         },
                     )
                 }));
@@ -270,20 +283,6 @@ impl ProgressTask {
                 Ok(true)
             }
 
-            ProgressTaskMessage::DiscoverTrack(id, track) => {
-                self.tracks.insert(
-                    id,
-                    TrackProgress {
-                        sort: (track.volume_number, track.track_number),
-                        state: None,
-                        last_known_bytes: 0,
-                        album_id: None,
-                    },
-                );
-
-                Ok(true)
-            }
-
             ProgressTaskMessage::Progress(update) => {
                 let Some(track) = self.tracks.get_mut(&update.track_id) else {
                     return Ok(false);
@@ -294,7 +293,11 @@ impl ProgressTask {
                     _ => track.last_known_bytes,
                 };
 
-                track.state = Some(update.state);
+                // stream info comes alongside the state, not instead of it
+                match update.state {
+                    ProgressState::Stream(info) => track.info = Some(info),
+                    state => track.state = Some(state),
+                }
 
                 if let Some(album_id) = track.album_id {
                     self.progress_update(album_id).await;
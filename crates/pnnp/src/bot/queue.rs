@@ -0,0 +1,177 @@
+use super::{Data, Error};
+use crate::{
+    bot::progress::{self, ProgressTaskMessage},
+    config::Config,
+    pipeline::Pipeline,
+    source::{MusicSource, SourceError},
+};
+use monochrome::{
+    album::Album,
+    id::{AlbumId, ArtistId, PlaylistId, TidalRef, TrackId},
+};
+use std::sync::Arc;
+use tokio::sync::mpsc;
+
+/// a batch of albums ready to be handed to the pipeline, with a human readable name for the batch
+pub struct Queued {
+    pub label: String,
+    pub albums: Vec<Album>,
+}
+
+pub async fn resolve(source: &dyn MusicSource, tidal_ref: TidalRef) -> anyhow::Result<Queued> {
+    Ok(match tidal_ref {
+        TidalRef::Track(id) => resolve_track(source, id).await?,
+        TidalRef::Album(id) => resolve_album(source, id).await?,
+        TidalRef::Artist(id) => resolve_artist(source, id).await?,
+        TidalRef::Playlist(id) => resolve_playlist(source, id).await?,
+        TidalRef::Video(_) => anyhow::bail!("videos can't be downloaded"),
+    })
+}
+
+pub async fn resolve_album(source: &dyn MusicSource, id: AlbumId) -> Result<Queued, SourceError> {
+    let album = source.album(id).await?;
+
+    Ok(Queued {
+        label: format!("{} - {}", album.artist.name, album.title),
+        albums: vec![album],
+    })
+}
+
+/// a track is downloaded as its album, narrowed down to just that track
+pub async fn resolve_track(source: &dyn MusicSource, id: TrackId) -> Result<Queued, SourceError> {
+    let track = source.track(id).await?;
+    let albums = fetch_albums(source, vec![(track.album.id, Some(vec![track.id]))]).await?;
+
+    Ok(Queued {
+        label: format!("{} - {}", track.artist.name, track.title),
+        albums,
+    })
+}
+
+pub async fn resolve_artist(source: &dyn MusicSource, id: ArtistId) -> Result<Queued, SourceError> {
+    let discography = source.artist(id).await?;
+
+    let wanted = discography
+        .albums
+        .iter()
+        .chain(&discography.singles)
+        .map(|a| (a.id, None))
+        .collect();
+
+    Ok(Queued {
+        label: format!("{} - discography", discography.artist.name),
+        albums: fetch_albums(source, wanted).await?,
+    })
+}
+
+pub async fn resolve_playlist(
+    source: &dyn MusicSource,
+    id: PlaylistId,
+) -> Result<Queued, SourceError> {
+    let playlist = source.playlist(id).await?;
+
+    // the pipeline works album by album, so group the playlist's tracks by album and only keep
+    // the ones that are actually on the playlist
+    let mut wanted: Vec<(AlbumId, Option<Vec<TrackId>>)> = Vec::new();
+    for track in &playlist.tracks {
+        match wanted.iter_mut().find(|(id, _)| *id == track.album.id) {
+            Some((_, Some(tracks))) => tracks.push(track.id),
+            _ => wanted.push((track.album.id, Some(vec![track.id]))),
+        }
+    }
+
+    Ok(Queued {
+        label: playlist.title,
+        albums: fetch_albums(source, wanted).await?,
+    })
+}
+
+/// fetches full albums one at a time (to go easy on the mirror), optionally narrowing each one
+/// down to a subset of its tracks
+async fn fetch_albums(
+    source: &dyn MusicSource,
+    wanted: Vec<(AlbumId, Option<Vec<TrackId>>)>,
+) -> Result<Vec<Album>, SourceError> {
+    let mut albums = Vec::with_capacity(wanted.len());
+
+    for (id, tracks) in wanted {
+        let mut album = source.album(id).await?;
+        if let Some(tracks) = tracks {
+            album.tracks.retain(|t| tracks.contains(&t.id));
+        }
+        albums.push(album);
+    }
+
+    Ok(albums)
+}
+
+/// runs every album through the pipeline concurrently. returns a description of each album that
+/// failed
+pub async fn download_albums(data: &Data, albums: Vec<Album>) -> Result<Vec<String>, Error> {
+    let downloads = albums.into_iter().map(|album| async move {
+        let msgs = progress::done_msgs(&album);
+        let title = album.title.clone();
+
+        if let Err(e) = handle_download(data.source.clone(), data.config.clone(), album, data).await
+        {
+            tracing::error!(error = %e, album = %title, "failed to download album");
+
+            for msg in msgs {
+                data.progress_tx.send(msg)?;
+            }
+
+            return Ok(Some(format!("{title}: {e}")));
+        }
+
+        anyhow::Ok(None)
+    });
+
+    Ok(futures::future::try_join_all(downloads)
+        .await?
+        .into_iter()
+        .flatten()
+        .collect())
+}
+
+async fn handle_download(
+    source: Arc<dyn MusicSource>,
+    config: Arc<Config>,
+    album: Album,
+    data: &Data,
+) -> anyhow::Result<()> {
+    let (tx, mut rx) = mpsc::unbounded_channel();
+
+    // data.progress_tx
+    //     .send(ProgressTaskMessage::DiscoverAlbum(id, music.clone()))?;
+
+    data.progress_tx
+        .send(ProgressTaskMessage::DiscoverAlbum(album.id, album.clone()))?;
+
+    let msgs = progress::done_msgs(&album);
+
+    let pipeline = Pipeline::new(
+        source,
+        album,
+        tx,
+        data.track_semaphore.clone(),
+        data.chunk_semaphore.clone(),
+        config,
+    );
+
+    let handle = tokio::spawn(pipeline.begin());
+
+    while let Some(update) = rx.recv().await {
+        data.progress_tx
+            .send(ProgressTaskMessage::Progress(update))?;
+    }
+
+    for handle in handle.await? {
+        handle.await??;
+    }
+
+    for msg in msgs {
+        data.progress_tx.send(msg)?;
+    }
+
+    Ok(())
+}
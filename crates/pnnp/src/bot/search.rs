@@ -1,13 +1,20 @@
 use super::{Context, Error};
+use crate::source::MusicSource;
 use crate::track_or_album::TrackOrAlbum;
+use monochrome::search::{SearchPage, SearchQuery, SearchType};
 use poise::ChoiceParameter;
 use poise::CreateReply;
 use poise::serenity_prelude::CreateActionRow;
+use poise::serenity_prelude::CreateButton;
 use poise::serenity_prelude::CreateSelectMenu;
 use poise::serenity_prelude::CreateSelectMenuKind;
 use poise::serenity_prelude::CreateSelectMenuOption;
 use unicode_ellipsis::truncate_str;
 
+pub const PAGE_PREFIX: &str = "search_page:";
+pub const ARTIST_SELECT: &str = "artist_select";
+pub const PLAYLIST_SELECT: &str = "playlist_select";
+
 #[derive(Debug, Clone, Copy, ChoiceParameter)]
 pub enum SearchKind {
     Single,
@@ -22,6 +29,13 @@ impl SearchKind {
         }
     }
 
+    pub fn search_type(&self) -> SearchType {
+        match self {
+            SearchKind::Single => SearchType::Tracks,
+            SearchKind::Album => SearchType::Albums,
+        }
+    }
+
     pub fn name(&self) -> &'static str {
         match self {
             SearchKind::Single => "track",
@@ -30,32 +44,120 @@ impl SearchKind {
     }
 }
 
-pub async fn search_command(ctx: Context<'_>, query: &str, kind: SearchKind) -> Result<(), Error> {
-    let client = &ctx.data().client;
+const PAGE_SIZE: u32 = 25;
+const MIXED_PAGE_SIZE: u32 = 10;
+
+pub struct SearchMessage {
+    pub content: String,
+    pub components: Vec<CreateActionRow>,
+}
+
+pub async fn search_command(
+    ctx: Context<'_>,
+    query: &str,
+    kind: Option<SearchKind>,
+) -> Result<(), Error> {
     ctx.defer().await?;
 
-    let music: Vec<TrackOrAlbum> = match kind {
-        SearchKind::Single => client
-            .search_tracks(query)
-            .await?
-            .into_iter()
-            .map(|t| TrackOrAlbum::Track(t))
-            .collect(),
+    let message = match kind {
+        Some(kind) => search_message(ctx.data().source.as_ref(), query, kind, 0).await?,
+        None => search_all_message(ctx.data().source.as_ref(), query).await?,
+    };
 
-        SearchKind::Album => client
-            .search_albums(query)
-            .await?
+    let Some(message) = message else {
+        ctx.say(format!(
+            "no {}s found",
+            kind.map(|k| k.name()).unwrap_or("result")
+        ))
+        .await?;
+        return Ok(());
+    };
+
+    ctx.send(
+        CreateReply::default()
+            .content(message.content)
+            .components(message.components),
+    )
+    .await?;
+
+    Ok(())
+}
+
+/// builds the select menu (and paging buttons, if there's more than one page) for a page of
+/// results. returns `None` if the page is empty
+pub async fn search_message(
+    source: &dyn MusicSource,
+    query: &str,
+    kind: SearchKind,
+    offset: u32,
+) -> Result<Option<SearchMessage>, Error> {
+    let page = source
+        .search(
+            SearchQuery::new(query)
+                .kind(kind.search_type())
+                .limit(PAGE_SIZE)
+                .offset(offset),
+        )
+        .await?;
+
+    let total = page.total();
+    let next_offset = page.next_offset(offset);
+
+    let music: Vec<TrackOrAlbum> = match page {
+        SearchPage::Tracks(page) => page.items.into_iter().map(TrackOrAlbum::Track).collect(),
+        SearchPage::Albums(page) => page
+            .items
             .into_iter()
             .map(|a| TrackOrAlbum::Album(a.into()))
             .collect(),
+        _ => Vec::new(),
     };
 
     if music.is_empty() {
-        ctx.say(format!("no {}s found", kind.name())).await?;
-        return Ok(());
+        return Ok(None);
+    }
+
+    let mut components = vec![CreateActionRow::SelectMenu(music_menu(kind, &music))];
+
+    let prev_offset = (offset > 0).then(|| offset.saturating_sub(PAGE_SIZE));
+    let buttons = [(prev_offset, "previous page"), (next_offset, "next page")]
+        .into_iter()
+        .filter_map(|(offset, label)| {
+            Some(CreateButton::new(page_id(kind, offset?, query)?).label(label))
+        })
+        .collect::<Vec<_>>();
+
+    if !buttons.is_empty() {
+        components.push(CreateActionRow::Buttons(buttons));
     }
 
-    let menu = CreateSelectMenu::new(
+    let shown = music.len() as u32;
+    let content = if let Some(total) = total
+        && total > shown
+    {
+        format!(
+            "found {total} {}s, showing {}-{}! please select one to be downloaded.",
+            kind.name(),
+            offset + 1,
+            offset + shown,
+        )
+    } else {
+        format!(
+            "found {} {}{}! please select one to be downloaded.",
+            shown,
+            kind.name(),
+            if shown == 1 { "" } else { "s" }
+        )
+    };
+
+    Ok(Some(SearchMessage {
+        content,
+        components,
+    }))
+}
+
+fn music_menu(kind: SearchKind, music: &[TrackOrAlbum]) -> CreateSelectMenu {
+    CreateSelectMenu::new(
         kind.as_id(),
         CreateSelectMenuKind::String {
             options: music
@@ -67,7 +169,7 @@ pub async fn search_command(ctx: Context<'_>, query: &str, kind: SearchKind) ->
                                 "{} - {}",
                                 music
                                     .artists()
-                                    .into_iter()
+                                    .iter()
                                     .map(|a| a.name.as_str())
                                     .collect::<Vec<_>>()
                                     .join(", "),
@@ -87,21 +189,135 @@ pub async fn search_command(ctx: Context<'_>, query: &str, kind: SearchKind) ->
         kind.name()
     ))
     .max_values(1)
-    .min_values(1);
+    .min_values(1)
+}
 
-    ctx.send(
-        CreateReply::default()
-            .content(format!(
-                "found {} {}{}! please select one to be downloaded.",
-                music.len(),
-                kind.name(),
-                if music.len() == 1 { "" } else { "s" }
-            ))
-            .components(vec![CreateActionRow::SelectMenu(menu)]),
-    )
-    .await?;
+/// a select menu per result type, for searches without a type filter. no paging here, the typed
+/// searches are for digging deeper
+pub async fn search_all_message(
+    source: &dyn MusicSource,
+    query: &str,
+) -> Result<Option<SearchMessage>, Error> {
+    let results = source
+        .search_all(SearchQuery::new(query).limit(MIXED_PAGE_SIZE))
+        .await?;
 
-    Ok(())
+    let tracks = results
+        .tracks
+        .items
+        .into_iter()
+        .map(TrackOrAlbum::Track)
+        .collect::<Vec<_>>();
+    let albums = results
+        .albums
+        .items
+        .into_iter()
+        .map(|a| TrackOrAlbum::Album(a.into()))
+        .collect::<Vec<_>>();
+
+    let mut components = Vec::new();
+    let mut found = Vec::new();
+
+    if !tracks.is_empty() {
+        found.push(count(tracks.len(), "track"));
+        components.push(CreateActionRow::SelectMenu(music_menu(
+            SearchKind::Single,
+            &tracks,
+        )));
+    }
+
+    if !albums.is_empty() {
+        found.push(count(albums.len(), "album"));
+        components.push(CreateActionRow::SelectMenu(music_menu(
+            SearchKind::Album,
+            &albums,
+        )));
+    }
+
+    if !results.artists.items.is_empty() {
+        found.push(count(results.artists.items.len(), "artist"));
+        components.push(CreateActionRow::SelectMenu(
+            CreateSelectMenu::new(
+                ARTIST_SELECT,
+                CreateSelectMenuKind::String {
+                    options: results
+                        .artists
+                        .items
+                        .iter()
+                        .map(|a| {
+                            CreateSelectMenuOption::new(
+                                truncate_str(&a.name, 100),
+                                a.id.to_string(),
+                            )
+                        })
+                        .collect(),
+                },
+            )
+            .placeholder("select an artist to download their discography...")
+            .max_values(1)
+            .min_values(1),
+        ));
+    }
+
+    if !results.playlists.items.is_empty() {
+        found.push(count(results.playlists.items.len(), "playlist"));
+        components.push(CreateActionRow::SelectMenu(
+            CreateSelectMenu::new(
+                PLAYLIST_SELECT,
+                CreateSelectMenuKind::String {
+                    options: results
+                        .playlists
+                        .items
+                        .iter()
+                        .map(|p| {
+                            let label = match &p.creator.name {
+                                Some(creator) => format!("{} - {}", creator, p.title),
+                                None => p.title.clone(),
+                            };
+                            CreateSelectMenuOption::new(truncate_str(&label, 100), p.id.to_string())
+                        })
+                        .collect(),
+                },
+            )
+            .placeholder("select a playlist...")
+            .max_values(1)
+            .min_values(1),
+        ));
+    }
+
+    if components.is_empty() {
+        return Ok(None);
+    }
+
+    Ok(Some(SearchMessage {
+        content: format!(
+            "found {}! please select one to be downloaded.",
+            found.join(", ")
+        ),
+        components,
+    }))
+}
+
+fn count(n: usize, name: &str) -> String {
+    format!("{n} {name}{}", if n == 1 { "" } else { "s" })
+}
+
+/// custom ids are capped at 100 characters, so very long queries just don't get paging buttons
+fn page_id(kind: SearchKind, offset: u32, query: &str) -> Option<String> {
+    let id = format!("{PAGE_PREFIX}{}:{offset}:{query}", kind.name());
+    (id.len() <= 100).then_some(id)
+}
+
+/// parses a custom id produced by [`page_id`]
+pub fn parse_page_id(id: &str) -> Option<(SearchKind, u32, &str)> {
+    let mut parts = id.strip_prefix(PAGE_PREFIX)?.splitn(3, ':');
+    let kind = match parts.next()? {
+        "track" => SearchKind::Single,
+        "album" => SearchKind::Album,
+        _ => return None,
+    };
+    let offset = parts.next()?.parse().ok()?;
+    Some((kind, offset, parts.next()?))
 }
 
 fn vowel_helper(s: &str) -> &str {
@@ -2,7 +2,16 @@ use figment::{
     Figment,
     providers::{Format, Toml},
 };
+use monochrome::{
+    art::ArtSize,
+    fixture::FixtureMode,
+    quality::AudioQuality,
+    ratelimit::RateLimitPolicy,
+    source::{CachedSource, EndpointSource, MirrorList, StaticSource, UptimeSource},
+};
+use reqwest::Url;
 use serde::Deserialize;
+use std::{path::PathBuf, time::Duration};
 
 #[derive(Debug, Deserialize)]
 pub struct Config {
@@ -10,6 +19,22 @@ pub struct Config {
     pub bot: BotConfig,
     pub downloads: DownloadConfig,
     pub navidrome: Option<NavidromeConfig>,
+    #[serde(default)]
+    pub endpoints: EndpointConfig,
+    pub cache: Option<CacheConfig>,
+    #[serde(default)]
+    pub source: SourceConfig,
+}
+
+/// where music comes from
+#[derive(Debug, Default, Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum SourceConfig {
+    /// the monochrome mirrors, set up by `[endpoints]`
+    #[default]
+    Monochrome,
+    /// albums already on disk, see [`DirectorySource`](crate::source::DirectorySource)
+    Directory { dir: PathBuf },
 }
 
 #[derive(Debug, Deserialize)]
@@ -28,6 +53,124 @@ pub struct BotConfig {
 pub struct DownloadConfig {
     pub chunk_concurrency: usize,
     pub track_concurrency: usize,
+    #[serde(default)]
+    pub quality: AudioQuality,
+    #[serde(default = "default_quality_fallback")]
+    pub quality_fallback: bool,
+    #[serde(default = "default_prefetch_window")]
+    pub prefetch_window: usize,
+    #[serde(default = "default_segment_fanout")]
+    pub segment_fanout: usize,
+    #[serde(default)]
+    pub art_size: ArtSize,
+}
+
+fn default_quality_fallback() -> bool {
+    true
+}
+
+fn default_prefetch_window() -> usize {
+    monochrome::download::DEFAULT_PREFETCH_WINDOW
+}
+
+fn default_segment_fanout() -> usize {
+    1
+}
+
+#[derive(Debug, Deserialize)]
+pub struct EndpointConfig {
+    #[serde(default = "default_uptime")]
+    pub uptime: bool,
+    pub uptime_url: Option<Url>,
+    #[serde(default)]
+    pub api: Vec<Url>,
+    #[serde(default)]
+    pub streaming: Vec<Url>,
+    pub cache: Option<PathBuf>,
+    #[serde(default)]
+    pub rate_limit: RateLimitPolicy,
+    pub fixtures: Option<FixtureMode>,
+}
+
+impl Default for EndpointConfig {
+    fn default() -> Self {
+        Self {
+            uptime: default_uptime(),
+            uptime_url: None,
+            api: Vec::new(),
+            streaming: Vec::new(),
+            cache: None,
+            rate_limit: RateLimitPolicy::default(),
+            fixtures: None,
+        }
+    }
+}
+
+fn default_uptime() -> bool {
+    true
+}
+
+impl EndpointConfig {
+    pub fn static_mirrors(&self) -> MirrorList {
+        MirrorList {
+            api: self.api.clone(),
+            streaming: self.streaming.clone(),
+        }
+    }
+
+    /// static mirrors, then the uptime worker, then whatever passed the last scan
+    pub fn sources(&self) -> Vec<Box<dyn EndpointSource>> {
+        let mut sources: Vec<Box<dyn EndpointSource>> = Vec::new();
+
+        let mirrors = self.static_mirrors();
+        if !mirrors.is_empty() {
+            sources.push(Box::new(StaticSource(mirrors)));
+        }
+
+        if self.uptime {
+            sources.push(Box::new(match &self.uptime_url {
+                Some(url) => UptimeSource::new(url.clone()),
+                None => UptimeSource::default(),
+            }));
+        }
+
+        let cache = self
+            .cache
+            .clone()
+            .or_else(|| dirs::cache_dir().map(|d| d.join("pnnp").join("mirrors.json")));
+        if let Some(path) = cache {
+            sources.push(Box::new(CachedSource::new(path)));
+        }
+
+        sources
+    }
+}
+
+/// every field falls back to monochrome's default when left out
+#[derive(Debug, Deserialize)]
+pub struct CacheConfig {
+    pub capacity: Option<usize>,
+    pub dir: Option<PathBuf>,
+    pub album_ttl_secs: Option<u64>,
+    pub track_ttl_secs: Option<u64>,
+    pub search_ttl_secs: Option<u64>,
+    pub artist_ttl_secs: Option<u64>,
+}
+
+impl From<&CacheConfig> for monochrome::cache::CacheConfig {
+    fn from(value: &CacheConfig) -> Self {
+        let default = Self::default();
+        let secs = |secs: Option<u64>, default| secs.map_or(default, Duration::from_secs);
+
+        Self {
+            capacity: value.capacity.unwrap_or(default.capacity),
+            dir: value.dir.clone(),
+            album_ttl: secs(value.album_ttl_secs, default.album_ttl),
+            track_ttl: secs(value.track_ttl_secs, default.track_ttl),
+            search_ttl: secs(value.search_ttl_secs, default.search_ttl),
+            artist_ttl: secs(value.artist_ttl_secs, default.artist_ttl),
+        }
+    }
 }
 
 #[derive(Debug, Deserialize)]
@@ -1,5 +1,8 @@
 use futures::{Stream, StreamExt};
-use monochrome::{artist::Artist, id::TrackId, track::Track};
+use monochrome::{
+    MonochromeError, artist::Artist, id::TrackId, manifest::StreamInfo, quality::AudioQuality,
+    track::Track,
+};
 use std::process::Stdio;
 use thiserror::Error;
 use tokio::{
@@ -20,6 +23,20 @@ pub enum TranscodeError {
 
     #[error("ffmpeg exited with non-zero status: {0}")]
     NonZeroExit(std::process::ExitStatus),
+
+    #[error("failed to download track: {0}")]
+    Download(#[from] MonochromeError),
+}
+
+impl TranscodeError {
+    /// ffmpeg failing usually means it was fed a broken download, which a fresh one can fix
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            TranscodeError::StdinOpen => false,
+            TranscodeError::StdinWrite(_) | TranscodeError::NonZeroExit(_) => true,
+            TranscodeError::Download(e) => e.is_retryable(),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Default)]
@@ -31,6 +48,9 @@ pub struct Metadata<'a> {
     pub track_number: Option<u32>,
     pub disc_number: Option<u32>,
     pub year: Option<u32>,
+    pub quality: Option<AudioQuality>,
+    pub stream: Option<&'a StreamInfo>,
+    pub lyrics: Option<&'a str>,
 }
 
 impl<'a> From<(&'a Track, &'a Artist, u32)> for Metadata<'a> {
@@ -47,6 +67,9 @@ impl<'a> From<(&'a Track, &'a Artist, u32)> for Metadata<'a> {
             track_number: Some(track.track_number),
             disc_number: Some(track.volume_number),
             year: Some(year),
+            quality: None,
+            stream: None,
+            lyrics: None,
         }
     }
 }
@@ -59,7 +82,7 @@ pub struct Transcoder<S> {
     output: String,
 }
 
-impl<S: Stream<Item = Result<bytes::Bytes, reqwest::Error>> + Unpin> Transcoder<S> {
+impl<S: Stream<Item = Result<bytes::Bytes, MonochromeError>> + Unpin> Transcoder<S> {
     pub fn new(
         stream: S,
         metadata: Metadata,
@@ -120,6 +143,38 @@ impl<S: Stream<Item = Result<bytes::Bytes, reqwest::Error>> + Unpin> Transcoder<
             args.push(format!("year={year}"));
         }
 
+        if let Some(quality) = metadata.quality {
+            args.push("-metadata".to_string());
+            args.push(format!("source_quality={quality}"));
+        }
+
+        if let Some(stream) = metadata.stream {
+            if let Some(codec) = stream.codec_name() {
+                args.push("-metadata".to_string());
+                args.push(format!("source_codec={codec}"));
+            }
+
+            if let Some(container) = &stream.container {
+                args.push("-metadata".to_string());
+                args.push(format!("source_container={container}"));
+            }
+
+            if let Some(bit_depth) = stream.bit_depth {
+                args.push("-metadata".to_string());
+                args.push(format!("source_bit_depth={bit_depth}"));
+            }
+
+            if let Some(sample_rate) = stream.sample_rate {
+                args.push("-metadata".to_string());
+                args.push(format!("source_sample_rate={sample_rate}"));
+            }
+        }
+
+        if let Some(lyrics) = metadata.lyrics {
+            args.push("-metadata".to_string());
+            args.push(format!("lyrics={lyrics}"));
+        }
+
         args.push(output.to_string());
 
         let child = Command::new("ffmpeg")
@@ -162,7 +217,7 @@ impl<S: Stream<Item = Result<bytes::Bytes, reqwest::Error>> + Unpin> Transcoder<
         let mut downloaded = 0;
 
         while let Some(chunk) = self.stream.next().await {
-            let chunk = chunk.map_err(|_| TranscodeError::StdinOpen)?;
+            let chunk = chunk?;
             stdin.write_all(&chunk).await?;
             downloaded += chunk.len() as u64;
 
@@ -0,0 +1,6 @@
+pub mod bot;
+pub mod config;
+pub mod ffmpeg;
+pub mod pipeline;
+pub mod source;
+mod track_or_album;
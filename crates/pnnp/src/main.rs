@@ -1,12 +1,12 @@
-mod bot;
-mod config;
-mod ffmpeg;
-mod pipeline;
-mod track_or_album;
-
-use std::time::Duration;
+use bytesize::ByteSize;
+use std::{sync::Arc, time::Duration};
 
 use monochrome::{Monochrome, endpoint::Endpoint};
+use pnnp::{
+    bot,
+    config::{self, Config, SourceConfig},
+    source::{DirectorySource, MusicSource},
+};
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
@@ -22,14 +22,68 @@ async fn main() -> anyhow::Result<()> {
 
     // bot::start(client, config).await?;
 
-    let endpoint = Endpoint::new();
-    endpoint.scan().await?;
-    let client = Monochrome::new(endpoint.clone());
+    let source: Arc<dyn MusicSource> = match &config.source {
+        SourceConfig::Monochrome => Arc::new(start_monochrome(&config).await),
+        SourceConfig::Directory { dir } => {
+            tracing::info!(dir = %dir.display(), "using albums from a directory");
+            Arc::new(DirectorySource::new(dir))
+        }
+    };
+
+    bot::start(source, config).await?;
+
+    Ok(())
+}
+
+async fn log_capabilities(endpoint: &Endpoint) {
+    for (url, capabilities) in endpoint.capabilities().await {
+        let throughput = capabilities
+            .throughput
+            .map(|t| format!("{}/s", ByteSize(t as u64)));
+
+        tracing::info!(
+            %url,
+            max_quality = ?capabilities.max_quality,
+            previews = capabilities.previews,
+            manifest = ?capabilities.manifest,
+            throughput = ?throughput,
+            "streaming mirror capabilities"
+        );
+    }
+}
+
+/// sets up the mirrors and keeps them fresh in the background
+async fn start_monochrome(config: &Config) -> Monochrome {
+    let mut endpoint = Endpoint::new()
+        .with_sources(config.endpoints.sources())
+        .with_mirrors(config.endpoints.static_mirrors())
+        .with_rate_limits(config.endpoints.rate_limit.clone());
+
+    if let Some(fixtures) = &config.endpoints.fixtures {
+        tracing::info!(?fixtures, "using fixtures");
+        endpoint = endpoint.with_fixtures(fixtures.clone());
+    }
+
+    // every source being down isn't fatal, requests just go to the mirrors we started with
+    if let Err(e) = endpoint.scan().await {
+        tracing::error!(error = %e, "initial endpoint scan failed");
+    }
+
+    let mut client = Monochrome::new(endpoint.clone())
+        .with_prefetch_window(config.downloads.prefetch_window)
+        .with_segment_fanout(config.downloads.segment_fanout);
+
+    if let Some(cache) = &config.cache {
+        client = client.with_cache(cache.into());
+    }
 
     let preferred_api = endpoint.preferred_api().await;
     let preferred_streaming = endpoint.preferred_streaming().await;
 
-    tracing::info!(preferred_api = %preferred_api, preferred_streaming = %preferred_streaming, "scan complete");
+    tracing::info!(preferred_api = %preferred_api, preferred_streaming = %preferred_streaming, "using endpoints");
+    log_capabilities(&endpoint).await;
+
+    endpoint.spawn_reprobe();
 
     tokio::spawn(async move {
         loop {
@@ -41,11 +95,10 @@ async fn main() -> anyhow::Result<()> {
                 let preferred_streaming = endpoint.preferred_streaming().await;
 
                 tracing::info!(preferred_api = %preferred_api, preferred_streaming = %preferred_streaming, "rescan complete");
+                log_capabilities(&endpoint).await;
             }
         }
     });
 
-    bot::start(client, config).await?;
-
-    Ok(())
+    client
 }
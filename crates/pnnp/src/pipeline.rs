@@ -1,12 +1,18 @@
 use crate::{
     config::Config,
     ffmpeg::{Metadata, TranscodeError, Transcoder},
-    track_or_album::TrackOrAlbum,
+    source::{ByteStream, MusicSource, SourceError},
 };
 use chrono::Datelike;
 use futures::StreamExt;
-use monochrome::{Monochrome, MonochromeError, album::Album, id::TrackId};
-use std::{path::PathBuf, sync::Arc};
+use monochrome::{MonochromeError, album::Album, id::TrackId, manifest::StreamInfo};
+use std::{
+    path::{Path, PathBuf},
+    sync::{
+        Arc,
+        atomic::{AtomicU64, Ordering},
+    },
+};
 use thiserror::Error;
 use tokio::{
     io::AsyncWriteExt,
@@ -14,7 +20,7 @@ use tokio::{
     task::JoinHandle,
 };
 use tokio_retry::{
-    Retry,
+    RetryIf,
     strategy::{ExponentialBackoff, jitter},
 };
 
@@ -26,6 +32,9 @@ pub enum PipelineError {
     #[error(transparent)]
     Monochrome(#[from] MonochromeError),
 
+    #[error(transparent)]
+    Source(#[from] SourceError),
+
     #[error("failed to acquire semaphore permit")]
     Semaphore(#[from] tokio::sync::AcquireError),
 
@@ -39,8 +48,22 @@ pub enum PipelineError {
     Reqwest(#[from] reqwest::Error),
 }
 
+impl PipelineError {
+    /// whether another attempt at the track could go differently
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            PipelineError::Transcode(e) => e.is_retryable(),
+            PipelineError::Monochrome(e) => e.is_retryable(),
+            PipelineError::Source(e) => e.is_retryable(),
+            PipelineError::Io(_) => true,
+            PipelineError::Reqwest(e) => monochrome::is_transient_request(e),
+            PipelineError::Semaphore(_) | PipelineError::Join(_) => false,
+        }
+    }
+}
+
 pub struct Pipeline {
-    client: Monochrome,
+    source: Arc<dyn MusicSource>,
     album: Album,
     tx: mpsc::UnboundedSender<ProgressUpdate>,
     track_semaphore: Arc<Semaphore>,
@@ -50,7 +73,7 @@ pub struct Pipeline {
 
 impl Pipeline {
     pub fn new(
-        client: Monochrome,
+        source: Arc<dyn MusicSource>,
         album: Album,
         tx: mpsc::UnboundedSender<ProgressUpdate>,
         track_semaphore: Arc<Semaphore>,
@@ -58,7 +81,7 @@ impl Pipeline {
         config: Arc<Config>,
     ) -> Self {
         Self {
-            client,
+            source,
             album,
             tx,
             track_semaphore,
@@ -67,19 +90,19 @@ impl Pipeline {
         }
     }
 
-    pub async fn begin(self) -> Vec<JoinHandle<Result<(), PipelineError>>> {
+    pub async fn begin(mut self) -> Vec<JoinHandle<Result<(), PipelineError>>> {
         let track_semaphore = self.track_semaphore;
         let chunk_semaphore = self.chunk_semaphore;
 
         let mut handles = Vec::new();
         let multidisc = self.album.tracks.iter().any(|t| t.volume_number > 1);
-        let album_folder = PathBuf::from(&self.config.output.dir)
-            .join(&path_compat(&self.album.artist.name))
-            .join(&path_compat(&format!(
-                "[{}] {}",
-                self.album.release_date.year(),
-                self.album.title
-            )));
+        let artist_folder =
+            PathBuf::from(&self.config.output.dir).join(path_compat(&self.album.artist.name));
+        let album_folder = artist_folder.join(path_compat(&format!(
+            "[{}] {}",
+            self.album.release_date.year(),
+            self.album.title
+        )));
 
         if let Err(e) = tokio::fs::create_dir_all(&album_folder).await {
             tracing::error!(
@@ -93,14 +116,15 @@ impl Pipeline {
         let year = self.album.release_date.year() as u32;
 
         let title = self.album.title.to_string();
-        let cover = self.album.cover;
         let artist = self.album.artist.clone();
-        let is_single = self.album.tracks.len() == 1;
+        // a single track picked out of a bigger album (e.g. from a playlist) still gets numbered
+        let is_single = self.album.kind == "SINGLE" && self.album.tracks.len() == 1;
+        let tracks = std::mem::take(&mut self.album.tracks);
 
-        for track in self.album.tracks {
+        for track in tracks {
             tracing::debug!(track = %track.title, "scheduling track for download and transcoding");
             let semaphore = track_semaphore.clone();
-            let client = self.client.clone();
+            let source = self.source.clone();
             let path = album_folder.join(path_compat(&if is_single {
                 format!("{}.opus", track.title)
             } else {
@@ -116,11 +140,11 @@ impl Pipeline {
 
             let tx = self.tx.clone();
 
-            if let Some(parent) = path.parent() {
-                if let Err(e) = tokio::fs::create_dir_all(parent).await {
-                    tracing::error!("failed to create directories for {}: {e}", path.display());
-                    continue;
-                }
+            if let Some(parent) = path.parent()
+                && let Err(e) = tokio::fs::create_dir_all(parent).await
+            {
+                tracing::error!("failed to create directories for {}: {e}", path.display());
+                continue;
             }
 
             if tokio::fs::metadata(&path).await.is_ok() {
@@ -130,6 +154,8 @@ impl Pipeline {
 
             let artist = artist.clone();
             let chunk_semaphore = chunk_semaphore.clone();
+            let quality = self.config.downloads.quality;
+            let quality_fallback = self.config.downloads.quality_fallback;
 
             let permit = semaphore.clone().acquire_owned().await.unwrap();
 
@@ -138,72 +164,206 @@ impl Pipeline {
                 let _permit = permit;
 
                 let inner = async move || {
+                    let lrc_path = path.with_extension("lrc");
                     let path = path.to_string_lossy();
-                    let dl_info = client.track_manifest(track.id).await?;
-                    let stream = client
-                        .download_track(&dl_info, chunk_semaphore.clone())
+                    let download = source
+                        .download(&track, quality, quality_fallback, chunk_semaphore.clone())
                         .await?;
-                    let transcoder = Transcoder::new(
-                        stream,
-                        Metadata::from((&track, &artist, year)),
-                        track.id,
-                        &path,
-                    )?;
+
+                    let obtained = download.quality.unwrap_or(quality);
+                    if obtained != quality {
+                        tracing::warn!(track = %track.title, requested = %quality, %obtained, "requested quality unavailable, fell back");
+                    } else {
+                        tracing::info!(track = %track.title, quality = %obtained, "obtained track manifest");
+                    }
+
+                    let lyrics = match source.lyrics(track.id).await {
+                        Ok(lyrics) => lyrics,
+                        Err(e) => {
+                            tracing::debug!(track = %track.title, error = %e, "no lyrics available");
+                            None
+                        }
+                    };
+                    let lrc = lyrics.as_ref().and_then(|l| l.to_lrc());
+
+                    let mut metadata = Metadata::from((&track, &artist, year));
+                    metadata.quality = Some(obtained);
+                    metadata.lyrics = lyrics
+                        .as_ref()
+                        .and_then(|l| l.plain.as_deref())
+                        .or(lrc.as_deref());
+                    metadata.stream = download.info.as_ref();
+                    if let Some(info) = &download.info {
+                        tracing::debug!(track = %track.title, format = %info, "stream info");
+                        tx.send(ProgressUpdate {
+                            track_id: track.id,
+                            state: ProgressState::Stream(info.clone()),
+                        })
+                        .ok();
+                    }
+
+                    let stats = download.stats;
+                    let transcoder = Transcoder::new(download.stream, metadata, track.id, &path)?;
                     transcoder.run(&tx).await?;
+
+                    if let Some(stats) = stats
+                        && (stats.segment_retries() > 0
+                            || stats.failovers() > 0
+                            || stats.resumes() > 0)
+                    {
+                        tracing::info!(
+                            track = %track.title,
+                            segment_retries = stats.segment_retries(),
+                            failovers = stats.failovers(),
+                            resumes = stats.resumes(),
+                            "track downloaded after retrying"
+                        );
+                    }
+
+                    if let Some(lrc) = &lrc {
+                        tokio::fs::write(&lrc_path, lrc).await?;
+                    }
+
                     Ok(())
                 };
 
-                Retry::spawn(retry_strategy, || async {
-                    if let Err(e) = inner().await {
-                        tracing::error!(error = %e, "error processing track, retrying...");
-                        Err(e)
-                    } else {
-                        Ok(())
-                    }
-                })
+                RetryIf::spawn(
+                    retry_strategy,
+                    || async {
+                        let res: Result<(), PipelineError> = inner().await;
+                        match &res {
+                            Err(e) if e.is_retryable() => {
+                                tracing::error!(error = %e, "error processing track, retrying...")
+                            }
+                            Err(e) => {
+                                tracing::error!(error = %e, "error processing track, giving up")
+                            }
+                            Ok(()) => {}
+                        }
+                        res
+                    },
+                    PipelineError::is_retryable,
+                )
                 .await
             });
 
             handles.push(handle);
         }
 
+        let art_size = self.config.downloads.art_size;
+
         {
-            let client = self.client.clone();
+            let source = self.source.clone();
+            let album = self.album.clone();
             let title = title.clone();
-            let cover = cover.clone();
 
             let album_art_handle: JoinHandle<Result<(), PipelineError>> =
                 tokio::spawn(async move {
                     let retry_strategy = ExponentialBackoff::from_millis(1000).map(jitter).take(5);
-                    Retry::spawn(retry_strategy, || async {
-                        let path = album_folder.join("cover.jpg").to_string_lossy().to_string();
+                    RetryIf::spawn(
+                        retry_strategy,
+                        || async {
+                            let path = album_folder.join("cover.jpg").to_string_lossy().to_string();
+                            if tokio::fs::metadata(&path).await.is_ok() {
+                                tracing::info!("skipping album art because it already exists");
+                                return Ok(());
+                            }
+
+                            tracing::info!(album = %title, "downloading album art...");
+                            let Some(stream) = source.album_art(&album, art_size).await? else {
+                                tracing::info!(album = %title, "album has no art");
+                                return Ok(());
+                            };
+
+                            write_stream(&path, stream).await?;
+                            tracing::info!(album = %title, "finished downloading album art");
+
+                            Ok(())
+                        },
+                        PipelineError::is_retryable,
+                    )
+                    .await
+                });
+
+            handles.push(album_art_handle);
+        }
+
+        // navidrome picks up artist.jpg from the artist folder
+        {
+            let source = self.source.clone();
+            let artist = self.album.artist;
+
+            let artist_picture_handle: JoinHandle<Result<(), PipelineError>> = tokio::spawn(
+                async move {
+                    let retry_strategy = ExponentialBackoff::from_millis(1000).map(jitter).take(5);
+                    RetryIf::spawn(retry_strategy, || async {
+                        let path = artist_folder.join("artist.jpg");
                         if tokio::fs::metadata(&path).await.is_ok() {
-                            tracing::info!("skipping album art because it already exists");
+                            tracing::debug!(artist = %artist.name, "skipping artist picture because it already exists");
                             return Ok(());
                         }
 
-                        tracing::info!(album = %title, "downloading album art...");
-                        let mut stream = client.art(cover).await?;
-                        let mut file = tokio::fs::File::create(&path).await?;
-                        while let Some(chunk) = stream.next().await {
-                            let chunk = chunk?;
-                            file.write_all(&chunk).await?;
-                        }
+                        tracing::info!(artist = %artist.name, "downloading artist picture...");
+                        let Some(stream) = source.artist_picture(artist.id, art_size).await? else {
+                            tracing::info!(artist = %artist.name, "artist has no picture");
+                            return Ok(());
+                        };
 
-                        tracing::info!(album = %title, "finished downloading album art");
+                        write_stream(&path, stream).await?;
+                        tracing::info!(artist = %artist.name, "finished downloading artist picture");
 
                         Ok(())
-                    })
+                    }, PipelineError::is_retryable)
                     .await
-                });
+                },
+            );
 
-            handles.push(album_art_handle);
+            handles.push(artist_picture_handle);
         }
 
         handles
     }
 }
 
+/// writes next to `path` and only moves the file into place once the stream is done, so a failed
+/// download never leaves a file behind that the existence checks would take for a finished one.
+/// every write gets its own part file, since albums by the same artist are downloaded
+/// concurrently and would otherwise write the same artist.jpg.part
+async fn write_stream(path: impl AsRef<Path>, stream: ByteStream) -> Result<(), PipelineError> {
+    static PARTS: AtomicU64 = AtomicU64::new(0);
+
+    let path = path.as_ref();
+    let mut part = path.as_os_str().to_owned();
+    part.push(format!(
+        ".{}.{}.part",
+        std::process::id(),
+        PARTS.fetch_add(1, Ordering::Relaxed)
+    ));
+    let part = PathBuf::from(part);
+
+    let written = write_part(&part, stream).await;
+    if written.is_err() {
+        let _ = tokio::fs::remove_file(&part).await;
+    }
+    written?;
+
+    tokio::fs::rename(&part, path).await?;
+    Ok(())
+}
+
+async fn write_part(part: &Path, mut stream: ByteStream) -> Result<(), PipelineError> {
+    let mut file = tokio::fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(part)
+        .await?;
+    while let Some(chunk) = stream.next().await {
+        file.write_all(&chunk?).await?;
+    }
+    file.flush().await?;
+    Ok(())
+}
+
 fn path_compat(s: &str) -> String {
     s.replace("/", "_")
         .replace("\\", "_")
@@ -238,6 +398,8 @@ pub struct ProgressUpdate {
 }
 
 pub enum ProgressState {
+    /// what the track is being downloaded as, sent before any bytes arrive
+    Stream(StreamInfo),
     Downloading(u64),
     Transcoding,
     Finished,
@@ -0,0 +1,337 @@
+use super::{ByteStream, MusicSource, SourceError, TrackDownload};
+use async_stream::try_stream;
+use bytes::BytesMut;
+use futures::future::BoxFuture;
+use monochrome::{
+    album::{Album, AlbumResult},
+    art::ArtSize,
+    artist::{ArtistDetails, Discography},
+    id::{AlbumId, ArtistId, TrackId},
+    lyrics::{self, Lyrics},
+    page::Page,
+    quality::AudioQuality,
+    search::{SearchPage, SearchQuery, SearchResults, SearchType},
+    track::Track,
+};
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+use tokio::{io::AsyncReadExt, sync::Semaphore};
+
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// albums laid out on disk, one folder each:
+///
+/// ```text
+/// <dir>/<anything>/album.json      an `Album`, as monochrome serializes it
+/// <dir>/<anything>/cover.jpg       optional
+/// <dir>/<anything>/<track id>.*    the audio, in whatever format ffmpeg can read
+/// <dir>/<anything>/<track id>.lrc  optional lyrics
+/// ```
+///
+/// the folder is read on every lookup, so albums can be added while the bot is running
+#[derive(Debug, Clone)]
+pub struct DirectorySource {
+    dir: PathBuf,
+}
+
+impl DirectorySource {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    async fn albums(&self) -> Result<Vec<(PathBuf, Album)>, SourceError> {
+        let mut albums = Vec::new();
+        let mut entries = tokio::fs::read_dir(&self.dir).await?;
+
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path().join("album.json");
+            let bytes = match tokio::fs::read(&path).await {
+                Ok(bytes) => bytes,
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+                Err(e) => return Err(e.into()),
+            };
+
+            match serde_json::from_slice::<Album>(&bytes) {
+                Ok(album) => albums.push((entry.path(), album)),
+                Err(e) => {
+                    tracing::warn!(path = %path.display(), error = %e, "skipping unreadable album")
+                }
+            }
+        }
+
+        // read_dir order isn't stable, and search pages need to be
+        albums.sort_by_key(|(_, album)| u64::from(album.id));
+        Ok(albums)
+    }
+
+    async fn find_album(&self, id: AlbumId) -> Result<(PathBuf, Album), SourceError> {
+        self.albums()
+            .await?
+            .into_iter()
+            .find(|(_, album)| album.id == id)
+            .ok_or_else(|| SourceError::NotFound(format!("album {id}")))
+    }
+
+    async fn find_track(&self, id: TrackId) -> Result<(PathBuf, Track), SourceError> {
+        self.albums()
+            .await?
+            .into_iter()
+            .find_map(|(dir, album)| {
+                let track = album.tracks.into_iter().find(|t| t.id == id)?;
+                Some((dir, track))
+            })
+            .ok_or_else(|| SourceError::NotFound(format!("track {id}")))
+    }
+
+    async fn search_page(&self, query: &SearchQuery) -> Result<SearchPage, SourceError> {
+        let albums = self.albums().await?;
+        let needle = query.text().to_lowercase();
+        let matches = |s: &str| s.to_lowercase().contains(&needle);
+        let (offset, limit) = query.page();
+
+        Ok(match query.search_type() {
+            SearchType::Tracks => SearchPage::Tracks(paginate(
+                albums
+                    .into_iter()
+                    .flat_map(|(_, album)| album.tracks)
+                    .filter(|t| matches(&t.title) || t.artists.iter().any(|a| matches(&a.name)))
+                    .collect(),
+                offset,
+                limit,
+            )),
+            SearchType::Albums => SearchPage::Albums(paginate(
+                albums
+                    .into_iter()
+                    .map(|(_, album)| album)
+                    .filter(|a| matches(&a.title) || matches(&a.artist.name))
+                    .map(album_result)
+                    .collect(),
+                offset,
+                limit,
+            )),
+            SearchType::Artists => {
+                let mut artists: Vec<ArtistDetails> = Vec::new();
+                for artist in albums.into_iter().flat_map(|(_, album)| album.artists) {
+                    if matches(&artist.name) && !artists.iter().any(|a| a.id == artist.id) {
+                        artists.push(ArtistDetails {
+                            id: artist.id,
+                            name: artist.name,
+                            picture: None,
+                            popularity: 0,
+                        });
+                    }
+                }
+
+                SearchPage::Artists(paginate(artists, offset, limit))
+            }
+            SearchType::Playlists => SearchPage::Playlists(paginate(Vec::new(), offset, limit)),
+        })
+    }
+}
+
+impl MusicSource for DirectorySource {
+    fn name(&self) -> &'static str {
+        "directory"
+    }
+
+    fn search(&self, query: SearchQuery) -> BoxFuture<'_, Result<SearchPage, SourceError>> {
+        Box::pin(async move { self.search_page(&query).await })
+    }
+
+    fn search_all(&self, query: SearchQuery) -> BoxFuture<'_, Result<SearchResults, SourceError>> {
+        Box::pin(async move {
+            let page = async |kind| self.search_page(&query.clone().kind(kind)).await;
+
+            match (
+                page(SearchType::Tracks).await?,
+                page(SearchType::Albums).await?,
+                page(SearchType::Artists).await?,
+                page(SearchType::Playlists).await?,
+            ) {
+                (
+                    SearchPage::Tracks(tracks),
+                    SearchPage::Albums(albums),
+                    SearchPage::Artists(artists),
+                    SearchPage::Playlists(playlists),
+                ) => Ok(SearchResults {
+                    tracks,
+                    albums,
+                    artists,
+                    playlists,
+                }),
+                _ => unreachable!("search returns the requested type"),
+            }
+        })
+    }
+
+    fn album(&self, id: AlbumId) -> BoxFuture<'_, Result<Album, SourceError>> {
+        Box::pin(async move { Ok(self.find_album(id).await?.1) })
+    }
+
+    fn track(&self, id: TrackId) -> BoxFuture<'_, Result<Track, SourceError>> {
+        Box::pin(async move { Ok(self.find_track(id).await?.1) })
+    }
+
+    fn artist(&self, id: ArtistId) -> BoxFuture<'_, Result<Discography, SourceError>> {
+        Box::pin(async move {
+            let albums = self
+                .albums()
+                .await?
+                .into_iter()
+                .map(|(_, album)| album)
+                .filter(|album| album.artists.iter().any(|a| a.id == id))
+                .collect::<Vec<_>>();
+
+            let Some(artist) = albums
+                .iter()
+                .flat_map(|album| &album.artists)
+                .find(|a| a.id == id)
+                .cloned()
+            else {
+                return Err(SourceError::NotFound(format!("artist {id}")));
+            };
+
+            let mut discography = Discography {
+                artist: ArtistDetails {
+                    id: artist.id,
+                    name: artist.name,
+                    picture: None,
+                    popularity: 0,
+                },
+                albums: Vec::new(),
+                singles: Vec::new(),
+                compilations: Vec::new(),
+            };
+
+            for album in albums {
+                let list = match album.kind.as_str() {
+                    "SINGLE" | "EP" => &mut discography.singles,
+                    "COMPILATION" => &mut discography.compilations,
+                    _ => &mut discography.albums,
+                };
+                list.push(album_result(album));
+            }
+
+            Ok(discography)
+        })
+    }
+
+    fn download<'a>(
+        &'a self,
+        track: &'a Track,
+        _quality: AudioQuality,
+        _fallback: bool,
+        _chunk_semaphore: Arc<Semaphore>,
+    ) -> BoxFuture<'a, Result<TrackDownload, SourceError>> {
+        Box::pin(async move {
+            let (dir, _) = self.find_track(track.id).await?;
+            let path = audio_file(&dir, track.id)
+                .await?
+                .ok_or_else(|| SourceError::NotFound(format!("audio for track {}", track.id)))?;
+
+            // files are whatever they are, there's no tier to report
+            Ok(TrackDownload {
+                quality: None,
+                stream: read_file(path).await?,
+                stats: None,
+                info: None,
+            })
+        })
+    }
+
+    fn lyrics(&self, id: TrackId) -> BoxFuture<'_, Result<Option<Lyrics>, SourceError>> {
+        Box::pin(async move {
+            let (dir, _) = self.find_track(id).await?;
+            let text = match tokio::fs::read_to_string(dir.join(format!("{id}.lrc"))).await {
+                Ok(text) => text,
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+                Err(e) => return Err(e.into()),
+            };
+
+            let synced = lyrics::parse_lrc(&text);
+            Ok(Some(Lyrics {
+                track_id: id,
+                provider: None,
+                plain: synced.is_empty().then_some(text),
+                synced,
+                right_to_left: false,
+            }))
+        })
+    }
+
+    fn album_art<'a>(
+        &'a self,
+        album: &'a Album,
+        _size: ArtSize,
+    ) -> BoxFuture<'a, Result<Option<ByteStream>, SourceError>> {
+        Box::pin(async move {
+            let (dir, _) = self.find_album(album.id).await?;
+            let path = dir.join("cover.jpg");
+            if tokio::fs::metadata(&path).await.is_err() {
+                return Ok(None);
+            }
+
+            Ok(Some(read_file(path).await?))
+        })
+    }
+}
+
+fn album_result(album: Album) -> AlbumResult {
+    AlbumResult {
+        id: album.id,
+        title: album.title,
+        release_date: album.release_date,
+        artists: album.artists,
+        cover: album.cover,
+        kind: album.kind,
+    }
+}
+
+fn paginate<T>(items: Vec<T>, offset: u32, limit: u32) -> Page<T> {
+    let total = items.len() as u32;
+
+    Page {
+        limit,
+        offset: Some(offset),
+        total_number_of_items: Some(total),
+        items: items
+            .into_iter()
+            .skip(offset as usize)
+            .take(limit as usize)
+            .collect(),
+    }
+}
+
+/// the file in `dir` named after the track, whatever its extension, skipping lyrics
+async fn audio_file(dir: &Path, id: TrackId) -> Result<Option<PathBuf>, SourceError> {
+    let stem = id.to_string();
+    let mut entries = tokio::fs::read_dir(dir).await?;
+
+    while let Some(entry) = entries.next_entry().await? {
+        let path = entry.path();
+        let is_track = path.file_stem().is_some_and(|s| s == stem.as_str());
+        let is_lyrics = path.extension().is_some_and(|e| e == "lrc");
+
+        if is_track && !is_lyrics {
+            return Ok(Some(path));
+        }
+    }
+
+    Ok(None)
+}
+
+async fn read_file(path: PathBuf) -> Result<ByteStream, SourceError> {
+    let mut file = tokio::fs::File::open(&path).await?;
+
+    Ok(Box::pin(try_stream! {
+        loop {
+            let mut buf = BytesMut::with_capacity(CHUNK_SIZE);
+            if file.read_buf(&mut buf).await? == 0 {
+                break;
+            }
+            yield buf.freeze();
+        }
+    }))
+}
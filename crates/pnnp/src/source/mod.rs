@@ -0,0 +1,127 @@
+mod directory;
+mod remote;
+
+pub use directory::DirectorySource;
+
+use bytes::Bytes;
+use futures::{future::BoxFuture, stream::BoxStream};
+use monochrome::{
+    MonochromeError,
+    album::Album,
+    art::ArtSize,
+    artist::Discography,
+    download::DownloadStats,
+    id::{AlbumId, ArtistId, PlaylistId, TrackId},
+    lyrics::Lyrics,
+    manifest::StreamInfo,
+    playlist::Playlist,
+    quality::AudioQuality,
+    search::{SearchPage, SearchQuery, SearchResults},
+    track::Track,
+};
+use std::sync::Arc;
+use thiserror::Error;
+use tokio::sync::Semaphore;
+
+pub type ByteStream = BoxStream<'static, Result<Bytes, MonochromeError>>;
+
+#[derive(Debug, Error)]
+pub enum SourceError {
+    #[error(transparent)]
+    Monochrome(#[from] MonochromeError),
+
+    #[error("io error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("json error: {0}")]
+    Json(#[from] serde_json::Error),
+
+    #[error("{0} not found")]
+    NotFound(String),
+
+    #[error("the {backend} source doesn't support {what}")]
+    Unsupported {
+        backend: &'static str,
+        what: &'static str,
+    },
+}
+
+impl SourceError {
+    /// whether the same call could succeed if tried again
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            SourceError::Monochrome(e) => e.is_retryable(),
+            SourceError::Io(_) => true,
+            SourceError::Json(_) | SourceError::NotFound(_) | SourceError::Unsupported { .. } => {
+                false
+            }
+        }
+    }
+}
+
+/// a track's audio, ready to be transcoded
+pub struct TrackDownload {
+    /// the tier that was actually obtained, if the source knows
+    pub quality: Option<AudioQuality>,
+    pub stream: ByteStream,
+    /// retry counters, for sources that download over the network
+    pub stats: Option<Arc<DownloadStats>>,
+    /// codec, bit depth and so on, if the source knows them up front
+    pub info: Option<StreamInfo>,
+}
+
+/// somewhere music can be looked up and downloaded from. everything the bot and the pipeline
+/// need goes through this, so any source feeds the same transcoding and tagging
+pub trait MusicSource: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    fn search(&self, query: SearchQuery) -> BoxFuture<'_, Result<SearchPage, SourceError>>;
+
+    /// every result type at once, with the query's limit and offset applied to each
+    fn search_all(&self, query: SearchQuery) -> BoxFuture<'_, Result<SearchResults, SourceError>>;
+
+    fn album(&self, id: AlbumId) -> BoxFuture<'_, Result<Album, SourceError>>;
+
+    fn track(&self, id: TrackId) -> BoxFuture<'_, Result<Track, SourceError>>;
+
+    fn artist(&self, id: ArtistId) -> BoxFuture<'_, Result<Discography, SourceError>>;
+
+    fn playlist(&self, _id: PlaylistId) -> BoxFuture<'_, Result<Playlist, SourceError>> {
+        Box::pin(async {
+            Err(SourceError::Unsupported {
+                backend: self.name(),
+                what: "playlists",
+            })
+        })
+    }
+
+    /// resolves the track's manifest and opens its byte stream. with `fallback`, a lower tier is
+    /// fine when `quality` isn't available
+    fn download<'a>(
+        &'a self,
+        track: &'a Track,
+        quality: AudioQuality,
+        fallback: bool,
+        chunk_semaphore: Arc<Semaphore>,
+    ) -> BoxFuture<'a, Result<TrackDownload, SourceError>>;
+
+    fn lyrics(&self, _id: TrackId) -> BoxFuture<'_, Result<Option<Lyrics>, SourceError>> {
+        Box::pin(async { Ok(None) })
+    }
+
+    /// the album's cover, or `None` if it doesn't have one
+    fn album_art<'a>(
+        &'a self,
+        album: &'a Album,
+        size: ArtSize,
+    ) -> BoxFuture<'a, Result<Option<ByteStream>, SourceError>>;
+
+    /// the artist's picture, or `None` if they don't have one
+    fn artist_picture(
+        &self,
+        _id: ArtistId,
+        _size: ArtSize,
+    ) -> BoxFuture<'_, Result<Option<ByteStream>, SourceError>> {
+        Box::pin(async { Ok(None) })
+    }
+}
@@ -0,0 +1,97 @@
+use super::{ByteStream, MusicSource, SourceError, TrackDownload};
+use futures::{StreamExt, future::BoxFuture};
+use monochrome::{
+    Monochrome,
+    album::Album,
+    art::ArtSize,
+    artist::Discography,
+    id::{AlbumId, ArtistId, PlaylistId, TrackId},
+    lyrics::Lyrics,
+    playlist::Playlist,
+    quality::AudioQuality,
+    search::{SearchPage, SearchQuery, SearchResults},
+    track::Track,
+};
+use std::sync::Arc;
+use tokio::sync::Semaphore;
+
+impl MusicSource for Monochrome {
+    fn name(&self) -> &'static str {
+        "monochrome"
+    }
+
+    fn search(&self, query: SearchQuery) -> BoxFuture<'_, Result<SearchPage, SourceError>> {
+        Box::pin(async move { Ok(Monochrome::search(self, query).await?) })
+    }
+
+    fn search_all(&self, query: SearchQuery) -> BoxFuture<'_, Result<SearchResults, SourceError>> {
+        Box::pin(async move { Ok(Monochrome::search_all(self, query).await?) })
+    }
+
+    fn album(&self, id: AlbumId) -> BoxFuture<'_, Result<Album, SourceError>> {
+        Box::pin(async move { Ok(Monochrome::album(self, id).await?) })
+    }
+
+    fn track(&self, id: TrackId) -> BoxFuture<'_, Result<Track, SourceError>> {
+        Box::pin(async move { Ok(Monochrome::track(self, id).await?) })
+    }
+
+    fn artist(&self, id: ArtistId) -> BoxFuture<'_, Result<Discography, SourceError>> {
+        Box::pin(async move { Ok(Monochrome::artist(self, id).await?) })
+    }
+
+    fn playlist(&self, id: PlaylistId) -> BoxFuture<'_, Result<Playlist, SourceError>> {
+        Box::pin(async move { Ok(Monochrome::playlist(self, id).await?) })
+    }
+
+    fn download<'a>(
+        &'a self,
+        track: &'a Track,
+        quality: AudioQuality,
+        fallback: bool,
+        chunk_semaphore: Arc<Semaphore>,
+    ) -> BoxFuture<'a, Result<TrackDownload, SourceError>> {
+        Box::pin(async move {
+            let manifest = if fallback {
+                self.track_manifest_with_fallback(track.id, quality).await?
+            } else {
+                self.track_manifest(track.id, quality).await?
+            };
+
+            let stream = self.download_track(&manifest, chunk_semaphore).await?;
+
+            Ok(TrackDownload {
+                quality: manifest.audio_quality,
+                stats: Some(stream.stats()),
+                info: Some(stream.info().clone()),
+                stream: stream.boxed(),
+            })
+        })
+    }
+
+    fn lyrics(&self, id: TrackId) -> BoxFuture<'_, Result<Option<Lyrics>, SourceError>> {
+        Box::pin(async move { Ok(Some(Monochrome::lyrics(self, id).await?)) })
+    }
+
+    fn album_art<'a>(
+        &'a self,
+        album: &'a Album,
+        size: ArtSize,
+    ) -> BoxFuture<'a, Result<Option<ByteStream>, SourceError>> {
+        Box::pin(async move {
+            let stream = Monochrome::album_art(self, album, size).await?;
+            Ok(Some(stream.boxed()))
+        })
+    }
+
+    fn artist_picture(
+        &self,
+        id: ArtistId,
+        size: ArtSize,
+    ) -> BoxFuture<'_, Result<Option<ByteStream>, SourceError>> {
+        Box::pin(async move {
+            let stream = Monochrome::artist_picture(self, id, size).await?;
+            Ok(stream.map(StreamExt::boxed))
+        })
+    }
+}
@@ -0,0 +1,146 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use futures::TryStreamExt;
+use monochrome::{album::Album, mock::sample_album, quality::AudioQuality};
+use pnnp::source::{ByteStream, DirectorySource, MusicSource, SourceError};
+use tokio::sync::Semaphore;
+
+fn scratch_dir() -> PathBuf {
+    std::env::temp_dir().join(format!("pnnp-directory-{}", uuid::Uuid::new_v4()))
+}
+
+/// writes `album.json` for an album with these tracks, the way [`DirectorySource`] expects it
+fn write_album(dir: &Path, id: u64, track_ids: &[u64]) -> PathBuf {
+    // the mock serves albums the way mirrors do, with the tracks wrapped in items
+    let mut album = sample_album(id, track_ids);
+    let items = album["items"].take();
+    album["tracks"] = items
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|i| i["item"].clone())
+        .collect();
+    let album: Album = serde_json::from_value(album).unwrap();
+
+    let folder = dir.join(format!("album {id}"));
+    std::fs::create_dir_all(&folder).unwrap();
+    std::fs::write(
+        folder.join("album.json"),
+        serde_json::to_vec(&album).unwrap(),
+    )
+    .unwrap();
+    folder
+}
+
+async fn collect(stream: ByteStream) -> Vec<u8> {
+    stream.try_collect::<Vec<_>>().await.unwrap().concat()
+}
+
+#[tokio::test]
+async fn looks_up_albums_and_tracks_by_id() {
+    let dir = scratch_dir();
+    write_album(&dir, 1, &[10, 11]);
+    let folder = write_album(&dir, 2, &[20]);
+    std::fs::write(folder.join("20.flac"), b"not really flac").unwrap();
+    std::fs::write(folder.join("20.lrc"), "[00:01.00]hello").unwrap();
+
+    let source = DirectorySource::new(&dir);
+
+    let album = source.album(1.into()).await.unwrap();
+    assert_eq!(album.tracks.len(), 2);
+
+    let track = source.track(20.into()).await.unwrap();
+    assert_eq!(track.album.id, 2.into());
+
+    // the lyrics sidecar shares the track's name, but isn't its audio
+    let download = source
+        .download(
+            &track,
+            AudioQuality::Lossless,
+            false,
+            Arc::new(Semaphore::new(1)),
+        )
+        .await
+        .unwrap();
+    assert_eq!(collect(download.stream).await, b"not really flac");
+
+    let lyrics = source.lyrics(20.into()).await.unwrap().unwrap();
+    assert_eq!(lyrics.synced.len(), 1);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn missing_tracks_are_not_found() {
+    let dir = scratch_dir();
+    write_album(&dir, 1, &[10]);
+    let source = DirectorySource::new(&dir);
+
+    assert!(matches!(
+        source.track(99.into()).await,
+        Err(SourceError::NotFound(_))
+    ));
+    assert!(matches!(
+        source.album(99.into()).await,
+        Err(SourceError::NotFound(_))
+    ));
+
+    // listed on the album, but there's no audio for it
+    let track = source.track(10.into()).await.unwrap();
+    let download = source
+        .download(
+            &track,
+            AudioQuality::Lossless,
+            false,
+            Arc::new(Semaphore::new(1)),
+        )
+        .await;
+    assert!(matches!(download, Err(SourceError::NotFound(_))));
+
+    // no sidecar just means no lyrics
+    assert!(source.lyrics(10.into()).await.unwrap().is_none());
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn reads_album_art_from_the_album_folder() {
+    let dir = scratch_dir();
+    let with_cover = write_album(&dir, 1, &[10]);
+    std::fs::write(with_cover.join("cover.jpg"), b"jpeg bytes").unwrap();
+    write_album(&dir, 2, &[20]);
+
+    let source = DirectorySource::new(&dir);
+
+    let album = source.album(1.into()).await.unwrap();
+    let art = source
+        .album_art(&album, Default::default())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(collect(art).await, b"jpeg bytes");
+
+    let album = source.album(2.into()).await.unwrap();
+    assert!(
+        source
+            .album_art(&album, Default::default())
+            .await
+            .unwrap()
+            .is_none()
+    );
+
+    // artist pictures aren't part of the layout
+    let artist = album.artist.id;
+    assert!(
+        source
+            .artist_picture(artist, Default::default())
+            .await
+            .unwrap()
+            .is_none()
+    );
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,152 @@
+use std::{
+    path::{Path, PathBuf},
+    process::Command,
+    sync::Arc,
+};
+
+use monochrome::mock::{Fault, MockServer, sample_album};
+use pnnp::{
+    config::Config,
+    pipeline::{Pipeline, PipelineError},
+};
+use tokio::sync::{Semaphore, mpsc};
+
+fn scratch_dir() -> PathBuf {
+    std::env::temp_dir().join(format!("pnnp-pipeline-{}", uuid::Uuid::new_v4()))
+}
+
+fn config(dir: &Path) -> Arc<Config> {
+    let config = format!(
+        r#"
+        [output]
+        dir = "{}"
+
+        [bot]
+        token = ""
+        progress_channel = 0
+
+        [downloads]
+        chunk_concurrency = 4
+        track_concurrency = 2
+        "#,
+        dir.display()
+    );
+
+    Arc::new(toml::from_str(&config).unwrap())
+}
+
+// transcoding needs ffmpeg, and so does making something for it to transcode
+fn ffmpeg_available() -> bool {
+    Command::new("ffmpeg")
+        .arg("-version")
+        .output()
+        .is_ok_and(|o| o.status.success())
+}
+
+fn flac() -> Vec<u8> {
+    let output = Command::new("ffmpeg")
+        .args([
+            "-f",
+            "lavfi",
+            "-i",
+            "sine=duration=1",
+            "-f",
+            "flac",
+            "pipe:1",
+        ])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    output.stdout
+}
+
+fn opus_files(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+
+    let mut files = Vec::new();
+    for path in entries.flatten().map(|e| e.path()) {
+        if path.is_dir() {
+            files.extend(opus_files(&path));
+        } else if path.extension().is_some_and(|ext| ext == "opus") {
+            files.push(path);
+        }
+    }
+    files
+}
+
+/// runs the album through the pipeline and waits for its tracks. the artwork tasks go to the real
+/// resources server, so they're dropped
+async fn run_album(server: &MockServer, dir: &Path) -> Vec<Result<(), PipelineError>> {
+    let monochrome = server.endpoint().api();
+    let album = monochrome.album(1).await.unwrap();
+    let tracks = album.tracks.len();
+
+    let (tx, _rx) = mpsc::unbounded_channel();
+    let handles = Pipeline::new(
+        Arc::new(monochrome),
+        album,
+        tx,
+        Arc::new(Semaphore::new(tracks)),
+        Arc::new(Semaphore::new(4)),
+        config(dir),
+    )
+    .begin()
+    .await;
+
+    let mut results = Vec::new();
+    for (i, handle) in handles.into_iter().enumerate() {
+        if i < tracks {
+            results.push(handle.await.unwrap());
+        } else {
+            handle.abort();
+        }
+    }
+    results
+}
+
+#[tokio::test]
+async fn gives_up_on_a_permanent_failure() {
+    let dir = scratch_dir();
+    let server = MockServer::start().await.unwrap();
+    server.data("/album?id=1", sample_album(1, &[10]));
+    server.bts_track(10, vec![0; 1024]);
+    server.inject("/files/10.flac", Fault::Status(404));
+
+    let results = run_album(&server, &dir).await;
+
+    let [Err(e)] = results.as_slice() else {
+        panic!("expected the track to fail");
+    };
+    assert!(!e.is_retryable(), "{e}");
+    assert_eq!(server.hits("/files/10.flac"), 1);
+    assert!(opus_files(&dir).is_empty());
+
+    std::fs::remove_dir_all(dir).ok();
+}
+
+#[tokio::test]
+async fn retries_a_transient_failure() {
+    if !ffmpeg_available() {
+        eprintln!("skipping, ffmpeg isn't installed");
+        return;
+    }
+
+    let dir = scratch_dir();
+    let server = MockServer::start().await.unwrap();
+    server.data("/album?id=1", sample_album(1, &[10]));
+    server.bts_track(10, flac());
+    server.inject("/files/10.flac", Fault::Status(503));
+
+    let results = run_album(&server, &dir).await;
+
+    assert!(
+        matches!(results.as_slice(), [Ok(())]),
+        "expected the track to succeed"
+    );
+    assert_eq!(server.hits("/files/10.flac"), 2);
+    assert_eq!(opus_files(&dir).len(), 1);
+
+    std::fs::remove_dir_all(dir).ok();
+}